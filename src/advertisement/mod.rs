@@ -0,0 +1,215 @@
+// Refer to SH2-Reference-Manual 1.3.1 and the SHTP protocol description
+//
+// After reset the hub advertises its applications on channel 0. The cargo
+// is report ID 0x00 followed by tag, length, value entries. A GUID tag
+// opens an application, channel tags are each followed by the channel's
+// name, and tag 0x80 holds the version string of the current application.
+
+use heapless::{String, Vec};
+
+const TAG_GUID: u8 = 1;
+const TAG_MAX_CARGO_PLUS_HEADER_WRITE: u8 = 2;
+const TAG_MAX_CARGO_PLUS_HEADER_READ: u8 = 3;
+const TAG_MAX_TRANSFER_WRITE: u8 = 4;
+const TAG_MAX_TRANSFER_READ: u8 = 5;
+const TAG_NORMAL_CHANNEL: u8 = 6;
+const TAG_WAKE_CHANNEL: u8 = 7;
+const TAG_APP_NAME: u8 = 8;
+const TAG_CHANNEL_NAME: u8 = 9;
+const TAG_APP_VERSION: u8 = 0x80;
+
+pub const ADVERTISEMENT_REPORT_ID: u8 = 0x00;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Application {
+    pub guid: u32,
+    pub name: String<16>,
+    pub version: String<16>,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Channel {
+    pub guid: u32,
+    pub number: u8,
+    pub name: String<16>,
+    pub wake: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Advertisement {
+    pub max_cargo_write: u16,
+    pub max_cargo_read: u16,
+    pub max_transfer_write: u16,
+    pub max_transfer_read: u16,
+    pub applications: Vec<Application, 4>,
+    pub channels: Vec<Channel, 8>,
+}
+
+impl Advertisement {
+    /// Parses the cargo of a channel 0 advertisement response. Returns
+    /// `None` if the cargo isn't an advertisement.
+    pub fn parse(cargo: &[u8]) -> Option<Self> {
+        if cargo.first() != Some(&ADVERTISEMENT_REPORT_ID) {
+            return None;
+        }
+
+        let mut out = Advertisement::default();
+        let mut guid = 0;
+        let mut pending: Option<(u8, bool)> = None;
+        let mut index = 1;
+        while index + 2 <= cargo.len() {
+            let tag = cargo[index];
+            let length = cargo[index + 1] as usize;
+            let Some(value) = cargo.get((index + 2)..(index + 2 + length)) else {
+                break;
+            };
+            index += 2 + length;
+
+            match tag {
+                TAG_GUID => {
+                    guid = le_u32(value);
+                    out.applications
+                        .push(Application {
+                            guid,
+                            name: String::new(),
+                            version: String::new(),
+                        })
+                        .ok();
+                }
+                TAG_MAX_CARGO_PLUS_HEADER_WRITE => out.max_cargo_write = le_u16(value),
+                TAG_MAX_CARGO_PLUS_HEADER_READ => out.max_cargo_read = le_u16(value),
+                TAG_MAX_TRANSFER_WRITE => out.max_transfer_write = le_u16(value),
+                TAG_MAX_TRANSFER_READ => out.max_transfer_read = le_u16(value),
+                TAG_NORMAL_CHANNEL => pending = value.first().map(|number| (*number, false)),
+                TAG_WAKE_CHANNEL => pending = value.first().map(|number| (*number, true)),
+                TAG_APP_NAME => {
+                    if let Some(app) = out.applications.last_mut() {
+                        app.name = to_string(value);
+                    }
+                }
+                TAG_CHANNEL_NAME => {
+                    if let Some((number, wake)) = pending.take() {
+                        out.channels
+                            .push(Channel {
+                                guid,
+                                number,
+                                name: to_string(value),
+                                wake,
+                            })
+                            .ok();
+                    }
+                }
+                TAG_APP_VERSION => {
+                    if let Some(app) = out.applications.last_mut() {
+                        app.version = to_string(value);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Some(out)
+    }
+
+    /// Looks up a channel number by its advertised name.
+    pub fn channel(&self, name: &str) -> Option<u8> {
+        self.channels
+            .iter()
+            .find(|channel| channel.name == name)
+            .map(|channel| channel.number)
+    }
+
+    pub fn application(&self, name: &str) -> Option<&Application> {
+        self.applications.iter().find(|app| app.name == name)
+    }
+
+    pub fn shtp_version(&self) -> Option<&str> {
+        self.application("SHTP").map(|app| app.version.as_str())
+    }
+
+    pub fn sh2_version(&self) -> Option<&str> {
+        self.application("sensorhub")
+            .map(|app| app.version.as_str())
+    }
+}
+
+/// Channel numbers the driver talks on. Defaults to the BNO08x layout until
+/// an advertisement says otherwise.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ChannelMap {
+    pub command: u8,
+    pub executable: u8,
+    pub control: u8,
+    pub input_normal: u8,
+    pub input_wake: u8,
+    pub input_gyro_rv: u8,
+}
+
+impl Default for ChannelMap {
+    fn default() -> Self {
+        ChannelMap {
+            command: 0,
+            executable: 1,
+            control: 2,
+            input_normal: 3,
+            input_wake: 4,
+            input_gyro_rv: 5,
+        }
+    }
+}
+
+impl ChannelMap {
+    pub fn from_advertisement(advertisement: &Advertisement) -> Self {
+        let default = ChannelMap::default();
+        ChannelMap {
+            command: advertisement.channel("command").unwrap_or(default.command),
+            executable: advertisement
+                .channel("device")
+                .unwrap_or(default.executable),
+            control: advertisement.channel("control").unwrap_or(default.control),
+            input_normal: advertisement
+                .channel("inputNormal")
+                .unwrap_or(default.input_normal),
+            input_wake: advertisement
+                .channel("inputWake")
+                .unwrap_or(default.input_wake),
+            input_gyro_rv: advertisement
+                .channel("inputGyroRv")
+                .unwrap_or(default.input_gyro_rv),
+        }
+    }
+}
+
+fn le_u16(value: &[u8]) -> u16 {
+    match value {
+        [low, high, ..] => u16::from_le_bytes([*low, *high]),
+        [low] => *low as u16,
+        [] => 0,
+    }
+}
+
+fn le_u32(value: &[u8]) -> u32 {
+    let mut bytes = [0u8; 4];
+    for (byte, value) in bytes.iter_mut().zip(value) {
+        *byte = *value;
+    }
+    u32::from_le_bytes(bytes)
+}
+
+fn to_string<const N: usize>(value: &[u8]) -> String<N> {
+    let end = value
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(value.len());
+    let mut out = String::new();
+    for c in core::str::from_utf8(&value[..end]).unwrap_or("").chars() {
+        if out.push(c).is_err() {
+            break;
+        }
+    }
+    out
+}
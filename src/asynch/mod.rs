@@ -0,0 +1,705 @@
+// Async twin of `BNO08x` for executors like Embassy. All protocol handling
+// lives in `DriverState`, this only awaits the bus and the delays instead
+// of blocking on them.
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::digital::Wait;
+use embedded_hal_async::i2c::I2c;
+use embedded_hal_async::spi::SpiDevice;
+use heapless::Vec;
+
+use crate::SensorError;
+use crate::advertisement::{Advertisement, ChannelMap};
+use crate::clock::Deadline;
+use crate::command::{
+    CalibrationConfig, Command, CommandRequest, CommandResponse, Dcd, ERROR_REPORT_CAPACITY,
+    ErrorRecord, SensorCounts, Tare, TareAxes, TareBasis,
+};
+use crate::config::{INPUT_TIMEOUT_MS, INTERRUPT_TIMEOUT_NS, Timeouts, WAIT_STEP_NS};
+use crate::data::{PACKET_CAPACITY, Packet, SequenceStats};
+use crate::frs::FRSDataRead;
+use crate::register::*;
+use crate::state::{self, DriverState, WaitStep};
+use crate::transport::{AsyncTransport, I2cTransport, NoInterrupt, SpiTransport};
+
+pub struct BNO08xAsync<T, D, const N: usize = PACKET_CAPACITY> {
+    transport: T,
+    delay: D,
+    state: DriverState<N>,
+}
+
+impl<I2C, D> BNO08xAsync<I2cTransport<I2C>, D>
+where
+    I2C: I2c,
+    D: DelayNs,
+{
+    pub fn new(i2c: I2C, delay: D, default_addr: bool) -> Self {
+        let address = if default_addr {
+            I2CAddress::Default as u8
+        } else {
+            I2CAddress::Alternate as u8
+        };
+        Self::new_with_address(i2c, delay, address)
+    }
+
+    /// Talks to the hub at any 7-bit `address`. To share the bus with other
+    /// hubs or sensors, hand each one its own device from `embedded-hal-bus`
+    /// (e.g. `RefCellDevice` or `CriticalSectionDevice`).
+    pub fn new_with_address(i2c: I2C, delay: D, address: u8) -> Self {
+        Self::from_transport(I2cTransport::new(i2c, address), delay)
+    }
+}
+
+impl<I2C, IP, D> BNO08xAsync<I2cTransport<I2C, IP>, D>
+where
+    I2C: I2c,
+    IP: Wait,
+    D: DelayNs,
+{
+    /// Like [`new`](BNO08xAsync::new), but awaits INT instead of polling
+    /// the bus.
+    pub fn new_with_interrupt(i2c: I2C, interrupt: IP, delay: D, default_addr: bool) -> Self {
+        let address = if default_addr {
+            I2CAddress::Default as u8
+        } else {
+            I2CAddress::Alternate as u8
+        };
+        Self::from_transport(I2cTransport::with_interrupt(i2c, address, interrupt), delay)
+    }
+}
+
+impl<I2C, RP, D> BNO08xAsync<I2cTransport<I2C, NoInterrupt, RP>, D>
+where
+    I2C: I2c,
+    RP: OutputPin,
+    D: DelayNs,
+{
+    /// Like [`new`](BNO08xAsync::new), but owns the hub's NRST line so
+    /// [`hard_reset_device`](BNO08xAsync::hard_reset_device) can recover it.
+    pub fn new_with_reset(i2c: I2C, reset: RP, delay: D, default_addr: bool) -> Self {
+        let address = if default_addr {
+            I2CAddress::Default as u8
+        } else {
+            I2CAddress::Alternate as u8
+        };
+        Self::from_transport(I2cTransport::new(i2c, address).with_reset(reset), delay)
+    }
+}
+
+impl<SPI, IP, WP, RP, D> BNO08xAsync<SpiTransport<SPI, IP, WP, RP>, D>
+where
+    SPI: SpiDevice,
+    IP: Wait,
+    WP: OutputPin,
+    RP: OutputPin,
+    D: DelayNs,
+{
+    pub fn new_spi(spi: SPI, interrupt: IP, wake: WP, reset: RP, delay: D) -> Self {
+        Self::from_transport(SpiTransport::new(spi, interrupt, wake, reset), delay)
+    }
+}
+
+impl<T, D, const N: usize> BNO08xAsync<T, D, N>
+where
+    T: AsyncTransport,
+    D: DelayNs,
+{
+    pub fn from_transport(transport: T, delay: D) -> Self {
+        BNO08xAsync {
+            transport,
+            delay,
+            state: DriverState::new(),
+        }
+    }
+
+    pub fn release(self) -> (T, D) {
+        (self.transport, self.delay)
+    }
+
+    /// Resets the hub over the executable channel and waits for it to
+    /// report back. Every report is disabled afterwards, and the driver
+    /// forgets them too: later hard or unsolicited resets won't restore
+    /// them.
+    pub async fn soft_reset_device(&mut self) -> Result<(), SensorError<T::Error>> {
+        self.drain().await?;
+        self.send_exec_command(ExecCommand::Reset).await?;
+        self.state.on_soft_reset();
+
+        self.wait_for_reset_complete().await
+    }
+
+    /// Pulses the hub's reset line and waits for it to come back, then
+    /// enables the features that were on before.
+    pub async fn hard_reset_device(&mut self) -> Result<(), SensorError<T::Error>> {
+        info!("BNO08x Device Resetting");
+        if !self
+            .transport
+            .hard_reset(&mut self.delay)
+            .await
+            .map_err(SensorError::Bus)?
+        {
+            warn!("Transport has no reset line");
+            return Err(SensorError::Unimplemented);
+        }
+        self.state.on_reset();
+
+        self.wait_for_reset_complete().await?;
+        info!("BNO08x Device Reset");
+        self.restore_features().await
+    }
+
+    pub async fn sleep_device(&mut self) -> Result<(), SensorError<T::Error>> {
+        self.send_exec_command(ExecCommand::Sleep).await
+    }
+
+    pub async fn wake_device(&mut self) -> Result<(), SensorError<T::Error>> {
+        self.send_exec_command(ExecCommand::On).await
+    }
+
+    async fn send_exec_command(
+        &mut self,
+        command: ExecCommand,
+    ) -> Result<(), SensorError<T::Error>> {
+        let channel = self.state.channels.executable;
+        self.send_packet_from_data(channel, &[command as u8]).await
+    }
+
+    /// Sends every remembered feature configuration to the hub again.
+    async fn restore_features(&mut self) -> Result<(), SensorError<T::Error>> {
+        for index in 0..self.state.feature_config.len() {
+            let (feature_id, interval, sens_specific) = self.state.feature_config[index];
+            self.enable_features(feature_id, interval, sens_specific)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn wait_for_reset_complete(&mut self) -> Result<(), SensorError<T::Error>> {
+        let timeout_ms = self.state.timeouts.reset_ms;
+        let mut deadline = Deadline::from_ms(timeout_ms);
+        loop {
+            // Bus errors are expected while the hub boots
+            if let Ok(packet) = self.read_packet_until(&mut deadline).await
+                && self.state.is_reset_complete(&packet)
+            {
+                debug!("Reset complete");
+                return Ok(());
+            }
+            self.idle(&mut deadline).await;
+            if deadline.end_pass() {
+                break;
+            }
+        }
+
+        Err(self.state.reset_timeout())
+    }
+
+    /// Takes whatever the hub has queued without waiting on INT, so
+    /// nothing sent before a reset is mistaken for its answer.
+    async fn drain(&mut self) -> Result<(), SensorError<T::Error>> {
+        let mut deadline = Deadline::from_ms(self.state.timeouts.response_ms);
+        loop {
+            match self.read_packet_until(&mut Deadline::new(0)).await {
+                Ok(packet) if packet.packet_length() == 0 => return Ok(()),
+                Err(SensorError::Bus(e)) => return Err(SensorError::Bus(e)),
+                _ => {}
+            }
+            // A hub streaming reports never runs dry
+            if deadline.end_pass() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Reads the next logical packet, joining continuation fragments.
+    pub async fn read_packet(&mut self) -> Result<Packet<N>, SensorError<T::Error>> {
+        self.read_packet_until(&mut Deadline::new(INTERRUPT_TIMEOUT_NS.into()))
+            .await
+    }
+
+    /// Reads the next logical packet, waiting on INT only as long as
+    /// `deadline` allows.
+    async fn read_packet_until(
+        &mut self,
+        deadline: &mut Deadline,
+    ) -> Result<Packet<N>, SensorError<T::Error>> {
+        if let Some(out) = self.state.take_complete() {
+            return Ok(out);
+        }
+
+        loop {
+            let timeout_ns = deadline.remaining_ns();
+            let out = self
+                .transport
+                .read_packet(&mut deadline.stopwatch(&mut self.delay), timeout_ns)
+                .await
+                .map_err(SensorError::Bus)?;
+            let out = self.state.receive(out);
+            self.hand_over_max_read();
+            if let Some(out) = out.map_err(SensorError::Packet)? {
+                return Ok(out);
+            }
+        }
+    }
+
+    /// Gives the hub a moment between reads that brought nothing.
+    async fn idle(&mut self, deadline: &mut Deadline) {
+        let ns = deadline.remaining_ns().min(WAIT_STEP_NS);
+        deadline.stopwatch(&mut self.delay).delay_ns(ns).await;
+    }
+
+    /// Passes the read limit from a new advertisement on to the transport.
+    fn hand_over_max_read(&mut self) {
+        if let Some(max_read) = self.state.max_read.take() {
+            self.transport.set_max_read(max_read);
+        }
+    }
+
+    /// The hub's last advertisement, if one has been seen since startup.
+    pub fn advertisement(&self) -> Option<&Advertisement> {
+        self.state.advertisement.as_ref()
+    }
+
+    pub fn channels(&self) -> ChannelMap {
+        self.state.channels
+    }
+
+    /// Inbound sequence number counters for `channel`.
+    pub fn sequence_stats(&self, channel: u8) -> Option<SequenceStats> {
+        self.state.seq_num_r.get(channel as usize).copied()
+    }
+
+    pub fn clear_sequence_stats(&mut self) {
+        self.state.seq_num_r = [SequenceStats::default(); 6];
+    }
+
+    /// Whether the hub reset on its own since the last call. The driver
+    /// enables the features again by itself, but anything else the hub
+    /// forgot (tare, calibration settings) is up to the application.
+    pub fn take_reset_event(&mut self) -> bool {
+        core::mem::take(&mut self.state.reset_event)
+    }
+
+    pub fn timeouts(&self) -> Timeouts {
+        self.state.timeouts
+    }
+
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) {
+        self.state.timeouts = timeouts;
+    }
+
+    pub async fn send_packet_from_data(
+        &mut self,
+        channel: u8,
+        data: &[u8],
+    ) -> Result<(), SensorError<T::Error>> {
+        let header = self
+            .state
+            .write_header(channel, data.len())
+            .map_err(SensorError::Packet)?;
+
+        let received = self
+            .transport
+            .write_packet(&header, data, &mut self.delay)
+            .await
+            .map_err(SensorError::Bus)?;
+        self.state.receive_during_write(received);
+        self.hand_over_max_read();
+        debug!("PACKET SENT");
+        Ok(())
+    }
+
+    pub async fn send_full_packet(
+        &mut self,
+        channel: u8,
+        packet: Packet<N>,
+    ) -> Result<(), SensorError<T::Error>> {
+        self.state.increment_seq_num(channel);
+
+        let received = self
+            .transport
+            .write_packet(packet.header(), packet.cargo(), &mut self.delay)
+            .await
+            .map_err(SensorError::Bus)?;
+        self.state.receive_during_write(received);
+        self.hand_over_max_read();
+        debug!("PACKET SENT");
+        Ok(())
+    }
+
+    async fn wait_for_packet(
+        &mut self,
+        channel: u8,
+        report_id: Option<SH2Read>,
+        timeout_ms: u32,
+    ) -> Result<Packet<N>, SensorError<T::Error>> {
+        self.wait_for_packet_where(channel, report_id, timeout_ms, |_| true)
+            .await
+    }
+
+    /// Like [`wait_for_packet`](Self::wait_for_packet), but also skips
+    /// packets `accept` turns down.
+    async fn wait_for_packet_where(
+        &mut self,
+        channel: u8,
+        report_id: Option<SH2Read>,
+        timeout_ms: u32,
+        accept: impl Fn(&Packet<N>) -> bool,
+    ) -> Result<Packet<N>, SensorError<T::Error>> {
+        let mut deadline = Deadline::from_ms(timeout_ms);
+        loop {
+            let read = self.read_packet_until(&mut deadline).await;
+            match state::wait_step(read, channel, report_id, &accept) {
+                WaitStep::Done(out) => return out,
+                WaitStep::Skip => {}
+                WaitStep::Idle => self.idle(&mut deadline).await,
+            }
+            if deadline.end_pass() {
+                return Err(state::timed_out(channel, report_id));
+            }
+        }
+    }
+
+    /// Sends a command request without waiting for an answer. Returns the
+    /// command sequence number it went out with.
+    pub async fn send_command(
+        &mut self,
+        request: CommandRequest,
+    ) -> Result<u8, SensorError<T::Error>> {
+        let (seq, data) = self.state.command_request(request);
+        let control = self.state.channels.control;
+        self.send_packet_from_data(control, &data).await?;
+        Ok(seq)
+    }
+
+    /// Sends a command request and waits for the hub's response to it.
+    pub async fn command(
+        &mut self,
+        request: CommandRequest,
+    ) -> Result<CommandResponse, SensorError<T::Error>> {
+        let seq = self.send_command(request).await?;
+        self.wait_for_command_response(request.command, seq).await
+    }
+
+    /// Waits for the response to request `seq`, dropping any others.
+    pub async fn wait_for_command_response(
+        &mut self,
+        command: Command,
+        seq: u8,
+    ) -> Result<CommandResponse, SensorError<T::Error>> {
+        let control = self.state.channels.control;
+        let timeout_ms = self.state.timeouts.response_ms;
+        let packet = self
+            .wait_for_packet_where(
+                control,
+                Some(SH2Read::CommandResponse),
+                timeout_ms,
+                |packet| state::answers(packet, command, seq),
+            )
+            .await?;
+        state::command_response(&packet)
+    }
+
+    /// Fetches the hub's queued errors at `severity` or more severe, 0
+    /// being the most severe. Errors beyond [`ERROR_REPORT_CAPACITY`] are
+    /// dropped.
+    pub async fn error_report(
+        &mut self,
+        severity: u8,
+    ) -> Result<Vec<ErrorRecord, ERROR_REPORT_CAPACITY>, SensorError<T::Error>> {
+        let seq = self.send_command(ErrorRecord::request(severity)).await?;
+        let mut errors = Vec::new();
+        loop {
+            let response = self
+                .wait_for_command_response(Command::ErrorReport, seq)
+                .await?;
+            if !state::collect_error(&mut errors, &response) {
+                return Ok(errors);
+            }
+        }
+    }
+
+    /// Reads the hub's sample counters for `sensor`.
+    pub async fn sensor_counts(
+        &mut self,
+        sensor: ReportId,
+    ) -> Result<SensorCounts, SensorError<T::Error>> {
+        let seq = self.send_command(SensorCounts::get_request(sensor)).await?;
+        let mut counts = SensorCounts::default();
+        // The counts come in two parts
+        for _ in 0..2 {
+            let response = self
+                .wait_for_command_response(Command::Counter, seq)
+                .await?;
+            counts.update(&response);
+        }
+        Ok(counts)
+    }
+
+    /// Zeroes the hub's sample counters for `sensor`.
+    pub async fn clear_sensor_counts(
+        &mut self,
+        sensor: ReportId,
+    ) -> Result<(), SensorError<T::Error>> {
+        self.send_command(SensorCounts::clear_request(sensor))
+            .await?;
+        Ok(())
+    }
+
+    /// Zeroes the orientation on `axes` of the `basis` rotation vector.
+    /// Lost at reset unless [`persist_tare`](Self::persist_tare) follows.
+    pub async fn tare_now(
+        &mut self,
+        axes: TareAxes,
+        basis: TareBasis,
+    ) -> Result<(), SensorError<T::Error>> {
+        self.send_command(Tare::now(axes, basis)).await?;
+        Ok(())
+    }
+
+    /// Saves the current tare to flash.
+    pub async fn persist_tare(&mut self) -> Result<(), SensorError<T::Error>> {
+        self.send_command(Tare::persist()).await?;
+        Ok(())
+    }
+
+    /// Sets the rotation applied to all outputs, as a unit quaternion.
+    /// All zeros clears it.
+    pub async fn set_reorientation(
+        &mut self,
+        x: f32,
+        y: f32,
+        z: f32,
+        w: f32,
+    ) -> Result<(), SensorError<T::Error>> {
+        self.send_command(Tare::set_reorientation(x, y, z, w))
+            .await?;
+        Ok(())
+    }
+
+    /// Saves the dynamic calibration data to flash now.
+    pub async fn save_dcd(&mut self) -> Result<(), SensorError<T::Error>> {
+        self.checked_command(Dcd::save()).await?;
+        Ok(())
+    }
+
+    /// Turns the hub's periodic save of the dynamic calibration data on or
+    /// off. With it off, calibration only survives a power cycle through
+    /// [`save_dcd`](Self::save_dcd). The hub sends no response to this
+    /// command (SH2-Reference-Manual 6.4.7), so it isn't confirmed.
+    pub async fn set_dcd_autosave(&mut self, enabled: bool) -> Result<(), SensorError<T::Error>> {
+        self.send_command(Dcd::periodic_save(enabled)).await?;
+        Ok(())
+    }
+
+    /// Turns the background calibration of each sensor on or off.
+    pub async fn set_calibration_config(
+        &mut self,
+        config: CalibrationConfig,
+    ) -> Result<(), SensorError<T::Error>> {
+        self.checked_command(config.set_request()).await?;
+        Ok(())
+    }
+
+    /// Reads which background calibrations are running.
+    pub async fn calibration_config(&mut self) -> Result<CalibrationConfig, SensorError<T::Error>> {
+        let response = self
+            .checked_command(CalibrationConfig::get_request())
+            .await?;
+        Ok(CalibrationConfig::from_response(&response))
+    }
+
+    /// Drops the dynamic calibration data and resets the hub, which then
+    /// starts calibrating from the saved data or defaults. Features that
+    /// were on are enabled again.
+    pub async fn clear_dcd_and_reset(&mut self) -> Result<(), SensorError<T::Error>> {
+        self.drain().await?;
+        self.send_command(Dcd::clear_and_reset()).await?;
+        self.state.on_reset();
+
+        self.wait_for_reset_complete().await?;
+        self.restore_features().await
+    }
+
+    /// Sends a command whose response carries a status in R0.
+    async fn checked_command(
+        &mut self,
+        request: CommandRequest,
+    ) -> Result<CommandResponse, SensorError<T::Error>> {
+        let response = self.command(request).await?;
+        state::check_status(request.command, response)
+    }
+
+    pub async fn read_product_id(&mut self) -> Result<bool, SensorError<T::Error>> {
+        debug!("READING P ID");
+        let timeout_ms = self.state.timeouts.response_ms;
+        let control = self.state.channels.control;
+        self.send_packet_from_data(control, &state::product_id_request())
+            .await?;
+
+        let out = self
+            .wait_for_packet(control, Some(SH2Read::ProductIDResponse), timeout_ms)
+            .await?;
+        Ok(state::has_product_id(out))
+    }
+
+    pub async fn enable_features(
+        &mut self,
+        feature_id: ReportId,
+        interval: Option<u32>,
+        sens_specific: Option<u32>,
+    ) -> Result<(), SensorError<T::Error>> {
+        let requests = self
+            .state
+            .feature_requests(feature_id, interval, sens_specific)?;
+        for (feature, interval, sens_specific) in requests {
+            self.set_feature(feature, interval, sens_specific).await?;
+        }
+        Ok(())
+    }
+
+    async fn set_feature(
+        &mut self,
+        feature_id: ReportId,
+        interval: Option<u32>,
+        sens_specific: Option<u32>,
+    ) -> Result<(), SensorError<T::Error>> {
+        let data_buffer = state::set_feature_command(feature_id, interval, sens_specific);
+        warn!("ENABLE FEATURES OUTPUT: {:?}", &data_buffer);
+        let timeout_ms = self.state.timeouts.response_ms;
+        let control = self.state.channels.control;
+        self.send_packet_from_data(control, &data_buffer).await?;
+
+        match self
+            .wait_for_packet(control, Some(SH2Read::GetFeatureResponse), timeout_ms)
+            .await
+        {
+            Ok(_) => {
+                self.state
+                    .remember_feature(feature_id, interval, sens_specific);
+                warn!("FEATURE ENABLED");
+                Ok(())
+            }
+            Err(e) => {
+                warn!("FAILED TO ENABLE FEATURE {:?}", feature_id);
+                Err(e)
+            }
+        }
+    }
+
+    /// Reads the next input report into the sensor values. `false` means
+    /// none arrived and the values are unchanged.
+    ///
+    /// Features lost to an unsolicited reset are enabled again before the
+    /// read. A restore that fails is tried again on the next call.
+    pub async fn update_sensors(&mut self) -> Result<bool, SensorError<T::Error>> {
+        // The hub forgets its features when it resets on its own. A reset
+        // during the restore asks for another one.
+        if self.state.take_restore()
+            && let Err(e) = self.restore_features().await
+        {
+            self.state.restore_failed();
+            return Err(e);
+        }
+
+        let input = self.state.channels.input_normal;
+        let read = self.wait_for_packet(input, None, INPUT_TIMEOUT_MS).await;
+        self.state.input_received(read)
+    }
+
+    /// Reads FRS record `record_id` into `words`. Returns how many words
+    /// the record holds, those past the end of `words` are dropped.
+    pub async fn frs_read(
+        &mut self,
+        record_id: FRSConfiguration,
+        words: &mut [u32],
+    ) -> Result<usize, SensorError<T::Error>> {
+        let mut frs_data = FRSDataRead::new(record_id);
+        let request = frs_data.generate_read_request();
+
+        let timeout_ms = self.state.timeouts.response_ms;
+        let control = self.state.channels.control;
+        self.send_packet_from_data(control, &request).await?;
+        // Records come two words per response
+        loop {
+            let packet = self
+                .wait_for_packet_where(
+                    control,
+                    Some(SH2Read::FrsReadResponse),
+                    timeout_ms,
+                    |packet| frs_data.answers(packet.cargo()),
+                )
+                .await?;
+            if frs_data.process_read_response(packet.cargo(), words)? {
+                info!("FRS RESPONSE : {:?}", frs_data);
+                return Ok(frs_data.word_count());
+            }
+        }
+    }
+}
+
+// The getters hand back the raw report tuples
+#[allow(clippy::type_complexity)]
+impl<T, D, const N: usize> BNO08xAsync<T, D, N>
+where
+    T: AsyncTransport,
+    D: DelayNs,
+{
+    pub async fn accelerometer(
+        &mut self,
+    ) -> Result<(Status, f32, f32, f32), SensorError<T::Error>> {
+        self.update_sensors().await?;
+        Ok(self.state.sensors.acceleration)
+    }
+
+    pub async fn raw_accelerometer(
+        &mut self,
+    ) -> Result<(Status, u16, u16, u16, u32), SensorError<T::Error>> {
+        self.update_sensors().await?;
+        Ok(self.state.sensors.accel_raw)
+    }
+
+    pub async fn gyroscope(&mut self) -> Result<(Status, f32, f32, f32), SensorError<T::Error>> {
+        self.update_sensors().await?;
+        Ok(self.state.sensors.gyroscope)
+    }
+
+    pub async fn raw_gyroscope(
+        &mut self,
+    ) -> Result<(Status, u16, u16, u16, u16, u32), SensorError<T::Error>> {
+        self.update_sensors().await?;
+        Ok(self.state.sensors.gyro_raw)
+    }
+
+    pub async fn magnetometer(&mut self) -> Result<(Status, f32, f32, f32), SensorError<T::Error>> {
+        self.update_sensors().await?;
+        Ok(self.state.sensors.magnetometer)
+    }
+
+    pub async fn raw_magnetomter(
+        &mut self,
+    ) -> Result<(Status, u16, u16, u16, u32), SensorError<T::Error>> {
+        self.update_sensors().await?;
+        Ok(self.state.sensors.mag_raw)
+    }
+
+    pub async fn linear_acceleration(
+        &mut self,
+    ) -> Result<(Status, f32, f32, f32), SensorError<T::Error>> {
+        self.update_sensors().await?;
+        Ok(self.state.sensors.linear_accel)
+    }
+
+    pub async fn gravity(&mut self) -> Result<(Status, f32, f32, f32), SensorError<T::Error>> {
+        self.update_sensors().await?;
+        Ok(self.state.sensors.gravity)
+    }
+
+    pub async fn quaternions(
+        &mut self,
+    ) -> Result<(Status, f32, f32, f32, f32), SensorError<T::Error>> {
+        self.update_sensors().await?;
+        Ok(self.state.sensors.quaternions)
+    }
+}
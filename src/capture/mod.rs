@@ -0,0 +1,296 @@
+// Capture format
+//
+// A capture is a plain sequence of records, one per SHTP transfer:
+//
+//   offset  size  field
+//   0       4     timestamp in microseconds, little endian
+//   4       1     direction, 0 = hub to host, 1 = host to hub
+//   5       2     frame length, little endian
+//   7       n     frame: the 4 byte SHTP header followed by the cargo
+//
+// Frames are stored without the I2C/SPI spacer, so a capture taken on any
+// bus replays the same way. Reads that returned nothing aren't recorded.
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal_async::delay::DelayNs as AsyncDelayNs;
+use embedded_io::{Read, ReadExactError, Write};
+
+use crate::data::Packet;
+use crate::error::CaptureError;
+use crate::transport::{AsyncTransport, Transport};
+
+pub const RECORD_HEADER_LENGTH: usize = 7;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum Direction {
+    FromHub = 0,
+    ToHub = 1,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RecordHeader {
+    pub timestamp_us: u32,
+    pub direction: Direction,
+    /// Length of the frame that follows, SHTP header included
+    pub length: u16,
+}
+
+impl RecordHeader {
+    pub fn encode(&self) -> [u8; RECORD_HEADER_LENGTH] {
+        let mut out = [0u8; RECORD_HEADER_LENGTH];
+        out[0..4].copy_from_slice(&self.timestamp_us.to_le_bytes());
+        out[4] = self.direction as u8;
+        out[5..7].copy_from_slice(&self.length.to_le_bytes());
+        out
+    }
+
+    pub fn decode(bytes: &[u8; RECORD_HEADER_LENGTH]) -> Option<Self> {
+        let direction = match bytes[4] {
+            0 => Direction::FromHub,
+            1 => Direction::ToHub,
+            _ => return None,
+        };
+        Some(RecordHeader {
+            timestamp_us: u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            direction,
+            length: u16::from_le_bytes([bytes[5], bytes[6]]),
+        })
+    }
+}
+
+/// Wraps a transport and logs every frame going through it to `sink`.
+///
+/// `clock` returns the current time in microseconds. Records the sink
+/// can't take are counted in [`dropped`](Self::dropped) rather than
+/// failing the transfer.
+pub struct Recorder<T, W, C> {
+    transport: T,
+    sink: W,
+    clock: C,
+    dropped: u32,
+}
+
+impl<T, W, C> Recorder<T, W, C>
+where
+    W: Write,
+    C: FnMut() -> u32,
+{
+    pub fn new(transport: T, sink: W, clock: C) -> Self {
+        Recorder {
+            transport,
+            sink,
+            clock,
+            dropped: 0,
+        }
+    }
+
+    pub fn release(self) -> (T, W) {
+        (self.transport, self.sink)
+    }
+
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+
+    fn record_read<const N: usize>(&mut self, packet: &Packet<N>) {
+        if packet.packet_length() > 0 {
+            self.record(Direction::FromHub, packet.header(), packet.cargo());
+        }
+    }
+
+    fn record(&mut self, direction: Direction, frame_header: &[u8; 4], cargo: &[u8]) {
+        let header = RecordHeader {
+            timestamp_us: (self.clock)(),
+            direction,
+            length: (frame_header.len() + cargo.len()) as u16,
+        };
+        let written = self
+            .sink
+            .write_all(&header.encode())
+            .and_then(|_| self.sink.write_all(frame_header))
+            .and_then(|_| self.sink.write_all(cargo));
+        if written.is_err() {
+            self.dropped = self.dropped.wrapping_add(1);
+        }
+    }
+}
+
+impl<T, W, C> Transport for Recorder<T, W, C>
+where
+    T: Transport,
+    W: Write,
+    C: FnMut() -> u32,
+{
+    type Error = T::Error;
+
+    fn read_packet<const N: usize, D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        timeout_ns: u32,
+    ) -> Result<Packet<N>, Self::Error> {
+        let out = self.transport.read_packet(delay, timeout_ns)?;
+        self.record_read(&out);
+        Ok(out)
+    }
+
+    fn write_packet<const N: usize, D: DelayNs>(
+        &mut self,
+        header: &[u8; 4],
+        cargo: &[u8],
+        delay: &mut D,
+    ) -> Result<Packet<N>, Self::Error> {
+        self.record(Direction::ToHub, header, cargo);
+        let out = self.transport.write_packet(header, cargo, delay)?;
+        self.record_read(&out);
+        Ok(out)
+    }
+
+    fn set_max_read(&mut self, max_read: u16) {
+        self.transport.set_max_read(max_read);
+    }
+
+    fn hard_reset<D: DelayNs>(&mut self, delay: &mut D) -> Result<bool, Self::Error> {
+        self.transport.hard_reset(delay)
+    }
+}
+
+impl<T, W, C> AsyncTransport for Recorder<T, W, C>
+where
+    T: AsyncTransport,
+    W: Write,
+    C: FnMut() -> u32,
+{
+    type Error = T::Error;
+
+    async fn read_packet<const N: usize, D: AsyncDelayNs>(
+        &mut self,
+        delay: &mut D,
+        timeout_ns: u32,
+    ) -> Result<Packet<N>, Self::Error> {
+        let out = self.transport.read_packet(delay, timeout_ns).await?;
+        self.record_read(&out);
+        Ok(out)
+    }
+
+    async fn write_packet<const N: usize, D: AsyncDelayNs>(
+        &mut self,
+        header: &[u8; 4],
+        cargo: &[u8],
+        delay: &mut D,
+    ) -> Result<Packet<N>, Self::Error> {
+        self.record(Direction::ToHub, header, cargo);
+        let out = self.transport.write_packet(header, cargo, delay).await?;
+        self.record_read(&out);
+        Ok(out)
+    }
+
+    fn set_max_read(&mut self, max_read: u16) {
+        self.transport.set_max_read(max_read);
+    }
+
+    async fn hard_reset<D: AsyncDelayNs>(&mut self, delay: &mut D) -> Result<bool, Self::Error> {
+        self.transport.hard_reset(delay).await
+    }
+}
+
+/// Feeds the hub side of a capture back to the driver. Frames the driver
+/// writes are accepted and discarded, and once the capture runs out every
+/// read comes back empty.
+pub struct ReplayTransport<R> {
+    capture: R,
+}
+
+impl<R> ReplayTransport<R>
+where
+    R: Read,
+{
+    pub fn new(capture: R) -> Self {
+        ReplayTransport { capture }
+    }
+
+    pub fn release(self) -> R {
+        self.capture
+    }
+
+    /// Reads exactly `buf.len()` bytes. `false` means the capture ended.
+    fn fill(&mut self, buf: &mut [u8]) -> Result<bool, CaptureError<R::Error>> {
+        match self.capture.read_exact(buf) {
+            Ok(()) => Ok(true),
+            Err(ReadExactError::UnexpectedEof) => Ok(false),
+            Err(ReadExactError::Other(e)) => Err(CaptureError::Io(e)),
+        }
+    }
+
+    fn skip(&mut self, mut length: usize) -> Result<bool, CaptureError<R::Error>> {
+        let mut scratch = [0u8; 32];
+        while length > 0 {
+            let chunk = length.min(scratch.len());
+            if !self.fill(&mut scratch[..chunk])? {
+                return Ok(false);
+            }
+            length -= chunk;
+        }
+        Ok(true)
+    }
+}
+
+impl<R> Transport for ReplayTransport<R>
+where
+    R: Read,
+{
+    type Error = CaptureError<R::Error>;
+
+    fn read_packet<const N: usize, D: DelayNs>(
+        &mut self,
+        _delay: &mut D,
+        _timeout_ns: u32,
+    ) -> Result<Packet<N>, Self::Error> {
+        loop {
+            let mut record = [0u8; RECORD_HEADER_LENGTH];
+            if !self.fill(&mut record)? {
+                return Ok(Packet::new(false));
+            }
+            let record = RecordHeader::decode(&record).ok_or(CaptureError::Format)?;
+            let length = record.length as usize;
+            if length < 4 {
+                return Err(CaptureError::Format);
+            }
+
+            if record.direction == Direction::ToHub {
+                if !self.skip(length)? {
+                    return Ok(Packet::new(false));
+                }
+                continue;
+            }
+
+            let mut header = [0u8; 4];
+            if !self.fill(&mut header)? {
+                return Ok(Packet::new(false));
+            }
+            let mut out = Packet::from_header(&header, false);
+            // Only part of the cargo was recorded for the first fragment of
+            // a split transfer
+            if length - 4 > out.as_mut_data(false).len() {
+                return Err(CaptureError::Format);
+            }
+            out.limit_transfer(length - 4);
+            if !self.fill(out.as_mut_data(false))? {
+                return Ok(Packet::new(false));
+            }
+
+            return Ok(out);
+        }
+    }
+
+    fn write_packet<const N: usize, D: DelayNs>(
+        &mut self,
+        _header: &[u8; 4],
+        _cargo: &[u8],
+        _delay: &mut D,
+    ) -> Result<Packet<N>, Self::Error> {
+        Ok(Packet::new(false))
+    }
+}
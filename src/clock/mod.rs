@@ -0,0 +1,74 @@
+// The driver has no clock of its own. A wait is timed by adding up every
+// delay made while it runs, both the driver's and the ones the transport
+// makes inside a read, such as polling INT.
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal_async::delay::DelayNs as AsyncDelayNs;
+
+use crate::config::{INTERRUPT_TIMEOUT_NS, WAIT_STEP_NS};
+
+/// Runs out once `timeout_ns` worth of delays have been counted.
+pub struct Deadline {
+    timeout_ns: u64,
+    elapsed_ns: u64,
+    pass_start_ns: u64,
+}
+
+impl Deadline {
+    pub fn new(timeout_ns: u64) -> Self {
+        Deadline {
+            timeout_ns,
+            elapsed_ns: 0,
+            pass_start_ns: 0,
+        }
+    }
+
+    pub fn from_ms(timeout_ms: u32) -> Self {
+        Self::new(timeout_ms as u64 * 1_000_000)
+    }
+
+    /// Time left, capped at one INT wait.
+    pub fn remaining_ns(&self) -> u32 {
+        let remaining = self.timeout_ns.saturating_sub(self.elapsed_ns);
+        remaining.min(INTERRUPT_TIMEOUT_NS as u64) as u32
+    }
+
+    /// Wraps `delay` so everything it waits counts against the deadline.
+    pub fn stopwatch<'a, D>(&'a mut self, delay: &'a mut D) -> Stopwatch<'a, D> {
+        Stopwatch {
+            delay,
+            elapsed_ns: &mut self.elapsed_ns,
+        }
+    }
+
+    /// Closes one pass of a wait loop and returns whether the deadline has
+    /// passed. A pass counts as at least [`WAIT_STEP_NS`], so reads that
+    /// return packets without ever delaying still run a wait out.
+    pub fn end_pass(&mut self) -> bool {
+        let floor = self.pass_start_ns + WAIT_STEP_NS as u64;
+        self.elapsed_ns = self.elapsed_ns.max(floor);
+        self.pass_start_ns = self.elapsed_ns;
+        self.elapsed_ns >= self.timeout_ns
+    }
+}
+
+/// A delay that adds up how long it was asked to wait. An async delay cut
+/// short, like the INT wait once INT asserts, isn't counted.
+pub struct Stopwatch<'a, D> {
+    delay: &'a mut D,
+    elapsed_ns: &'a mut u64,
+}
+
+impl<D: DelayNs> DelayNs for Stopwatch<'_, D> {
+    fn delay_ns(&mut self, ns: u32) {
+        self.delay.delay_ns(ns);
+        *self.elapsed_ns += ns as u64;
+    }
+}
+
+impl<D: AsyncDelayNs> AsyncDelayNs for Stopwatch<'_, D> {
+    async fn delay_ns(&mut self, ns: u32) {
+        self.delay.delay_ns(ns).await;
+        *self.elapsed_ns += ns as u64;
+    }
+}
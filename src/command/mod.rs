@@ -0,0 +1,386 @@
+// Refer to SH2-Reference-Manual 6.3.8 - 6.4
+//
+// Commands go out as a 12 byte Command Request (0xF2) on the control
+// channel:
+//
+//   0: report ID    1: report sequence number    2: command
+//   3..12: parameters P0..P8
+//
+// and come back as a 16 byte Command Response (0xF1):
+//
+//   0: report ID    1: report sequence number    2: command
+//   3: sequence number of the request being answered
+//   4: response sequence number, counts up for multi part responses
+//   5..16: response fields R0..R10
+//
+// Bit 7 of the command byte marks a response the hub sent on its own,
+// e.g. the Initialize response after a reset.
+
+use crate::config::QUAT_SCALAR_Q_POINT;
+use crate::register::{Register, ReportId, SH2Read, SH2Write};
+
+pub const COMMAND_REQUEST_LENGTH: usize = 12;
+pub const COMMAND_RESPONSE_LENGTH: usize = 16;
+
+/// Most error records kept from one error report
+pub const ERROR_REPORT_CAPACITY: usize = 16;
+
+const UNSOLICITED: u8 = 0x80;
+/// Error source marking the end of an error report
+const NO_ERROR: u8 = 0xFF;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum Command {
+    ErrorReport = 0x01,
+    Counter = 0x02,
+    Tare = 0x03,
+    Initialize = 0x04,
+    SaveDcd = 0x06,
+    MeCalibration = 0x07,
+    DcdPeriodicSave = 0x09,
+    Oscillator = 0x0A,
+    ClearDcdAndReset = 0x0B,
+}
+
+impl TryFrom<u8> for Command {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x01 => Ok(Command::ErrorReport),
+            0x02 => Ok(Command::Counter),
+            0x03 => Ok(Command::Tare),
+            0x04 => Ok(Command::Initialize),
+            0x06 => Ok(Command::SaveDcd),
+            0x07 => Ok(Command::MeCalibration),
+            0x09 => Ok(Command::DcdPeriodicSave),
+            0x0A => Ok(Command::Oscillator),
+            0x0B => Ok(Command::ClearDcdAndReset),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CommandRequest {
+    pub command: Command,
+    pub params: [u8; 9],
+}
+
+impl CommandRequest {
+    pub fn new(command: Command, params: [u8; 9]) -> Self {
+        CommandRequest { command, params }
+    }
+
+    /// Lays out the report with `seq` as the command sequence number.
+    pub fn encode(&self, seq: u8) -> [u8; COMMAND_REQUEST_LENGTH] {
+        let mut out = [0u8; COMMAND_REQUEST_LENGTH];
+        out[0] = Register::Write(SH2Write::CommandRequest).addr();
+        out[1] = seq;
+        out[2] = self.command as u8;
+        out[3..].copy_from_slice(&self.params);
+        out
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CommandResponse {
+    /// Command ID with the unsolicited flag stripped
+    pub command: u8,
+    pub unsolicited: bool,
+    /// Sequence number of the request this answers
+    pub command_seq: u8,
+    pub response_seq: u8,
+    /// R0..R10
+    pub fields: [u8; 11],
+}
+
+impl CommandResponse {
+    /// Parses a Command Response from report cargo. Returns `None` for any
+    /// other report.
+    pub fn parse(cargo: &[u8]) -> Option<Self> {
+        let cargo = cargo.get(..COMMAND_RESPONSE_LENGTH)?;
+        if cargo[0] != Register::Read(SH2Read::CommandResponse).addr() {
+            return None;
+        }
+
+        let mut fields = [0u8; 11];
+        fields.copy_from_slice(&cargo[5..]);
+        Some(CommandResponse {
+            command: cargo[2] & !UNSOLICITED,
+            unsolicited: cargo[2] & UNSOLICITED != 0,
+            command_seq: cargo[3],
+            response_seq: cargo[4],
+            fields,
+        })
+    }
+
+    /// Whether this answers request `seq` for `command`.
+    pub fn answers(&self, command: Command, seq: u8) -> bool {
+        !self.unsolicited && self.command == command as u8 && self.command_seq == seq
+    }
+
+    /// R0, which most commands use for their status. Zero means success.
+    pub fn status(&self) -> u8 {
+        self.fields[0]
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ErrorSource {
+    MotionEngine,
+    MotionHub,
+    SensorHub,
+    Chip,
+    Other(u8),
+}
+
+impl From<u8> for ErrorSource {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => ErrorSource::MotionEngine,
+            2 => ErrorSource::MotionHub,
+            3 => ErrorSource::SensorHub,
+            4 => ErrorSource::Chip,
+            other => ErrorSource::Other(other),
+        }
+    }
+}
+
+/// One entry of the hub's error queue, see [`Command::ErrorReport`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ErrorRecord {
+    /// 0 is the most severe
+    pub severity: u8,
+    pub sequence: u8,
+    pub source: ErrorSource,
+    pub error: u8,
+    pub module: u8,
+    pub code: u8,
+}
+
+impl ErrorRecord {
+    /// Decodes one error report response. `None` marks the end of the
+    /// list.
+    pub fn from_response(response: &CommandResponse) -> Option<Self> {
+        let fields = &response.fields;
+        if fields[2] == NO_ERROR {
+            return None;
+        }
+        Some(ErrorRecord {
+            severity: fields[0],
+            sequence: fields[1],
+            source: ErrorSource::from(fields[2]),
+            error: fields[3],
+            module: fields[4],
+            code: fields[5],
+        })
+    }
+
+    /// Request for every error at `severity` or more severe.
+    pub fn request(severity: u8) -> CommandRequest {
+        let mut params = [0u8; 9];
+        params[0] = severity;
+        CommandRequest::new(Command::ErrorReport, params)
+    }
+}
+
+const COUNTER_GET: u8 = 0x00;
+const COUNTER_CLEAR: u8 = 0x01;
+
+/// Sample counters of one sensor, see [`Command::Counter`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SensorCounts {
+    /// Samples the underlying source offered
+    pub offered: u32,
+    /// Offered samples that arrived while the sensor was on ("On" in the
+    /// manual)
+    pub produced: u32,
+    /// Produced samples that passed the decimation filter
+    pub accepted: u32,
+    /// Accepted samples that were reported to the host
+    pub attained: u32,
+}
+
+impl SensorCounts {
+    pub fn get_request(sensor: ReportId) -> CommandRequest {
+        CommandRequest::new(
+            Command::Counter,
+            [COUNTER_GET, sensor as u8, 0, 0, 0, 0, 0, 0, 0],
+        )
+    }
+
+    pub fn clear_request(sensor: ReportId) -> CommandRequest {
+        CommandRequest::new(
+            Command::Counter,
+            [COUNTER_CLEAR, sensor as u8, 0, 0, 0, 0, 0, 0, 0],
+        )
+    }
+
+    /// Takes the counts from one of the two responses to a get request.
+    pub fn update(&mut self, response: &CommandResponse) {
+        let f = &response.fields;
+        let first = u32::from_le_bytes([f[0], f[1], f[2], f[3]]);
+        let second = u32::from_le_bytes([f[4], f[5], f[6], f[7]]);
+        if response.response_seq == 0 {
+            self.offered = first;
+            self.accepted = second;
+        } else {
+            self.produced = first;
+            self.attained = second;
+        }
+    }
+}
+
+const TARE_NOW: u8 = 0x00;
+const TARE_PERSIST: u8 = 0x01;
+const TARE_SET_REORIENTATION: u8 = 0x02;
+
+/// Axes to tare, combine with `|`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TareAxes(pub u8);
+
+impl TareAxes {
+    pub const X: TareAxes = TareAxes(1 << 0);
+    pub const Y: TareAxes = TareAxes(1 << 1);
+    pub const Z: TareAxes = TareAxes(1 << 2);
+    pub const ALL: TareAxes = TareAxes(0b111);
+}
+
+impl core::ops::BitOr for TareAxes {
+    type Output = TareAxes;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        TareAxes(self.0 | rhs.0)
+    }
+}
+
+/// Rotation vector the tare is taken against.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum TareBasis {
+    RotationVector = 0,
+    GameRotationVector = 1,
+    GeomagneticRotationVector = 2,
+}
+
+/// Requests for [`Command::Tare`]. The hub doesn't answer any of them.
+pub struct Tare;
+
+impl Tare {
+    pub fn now(axes: TareAxes, basis: TareBasis) -> CommandRequest {
+        CommandRequest::new(
+            Command::Tare,
+            [TARE_NOW, axes.0, basis as u8, 0, 0, 0, 0, 0, 0],
+        )
+    }
+
+    pub fn persist() -> CommandRequest {
+        CommandRequest::new(Command::Tare, [TARE_PERSIST, 0, 0, 0, 0, 0, 0, 0, 0])
+    }
+
+    /// Rotation applied to every output, as a unit quaternion. All zeros
+    /// clears it.
+    pub fn set_reorientation(x: f32, y: f32, z: f32, w: f32) -> CommandRequest {
+        let mut params = [0u8; 9];
+        params[0] = TARE_SET_REORIENTATION;
+        for (slot, value) in params[1..].chunks_mut(2).zip([x, y, z, w]) {
+            let q14 = (value * (1 << QUAT_SCALAR_Q_POINT) as f32) as i16;
+            slot.copy_from_slice(&q14.to_le_bytes());
+        }
+        CommandRequest::new(Command::Tare, params)
+    }
+}
+
+const DCD_AUTOSAVE_ENABLE: u8 = 0x00;
+const DCD_AUTOSAVE_DISABLE: u8 = 0x01;
+
+/// Requests for the dynamic calibration data (DCD) commands.
+pub struct Dcd;
+
+impl Dcd {
+    /// Saves the DCD to flash now. Answered with a status in R0.
+    pub fn save() -> CommandRequest {
+        CommandRequest::new(Command::SaveDcd, [0; 9])
+    }
+
+    /// Turns the hub's own periodic DCD save on or off. The hub doesn't
+    /// answer this one (SH2-Reference-Manual 6.4.7).
+    pub fn periodic_save(enabled: bool) -> CommandRequest {
+        let mut params = [0u8; 9];
+        params[0] = if enabled {
+            DCD_AUTOSAVE_ENABLE
+        } else {
+            DCD_AUTOSAVE_DISABLE
+        };
+        CommandRequest::new(Command::DcdPeriodicSave, params)
+    }
+
+    /// Clears the DCD in RAM and resets the hub. Answered with a reset
+    /// rather than a command response.
+    pub fn clear_and_reset() -> CommandRequest {
+        CommandRequest::new(Command::ClearDcdAndReset, [0; 9])
+    }
+}
+
+const ME_CALIBRATION_CONFIGURE: u8 = 0x00;
+const ME_CALIBRATION_GET: u8 = 0x01;
+
+/// Which MotionEngine calibrations run in the background, see
+/// [`Command::MeCalibration`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CalibrationConfig {
+    pub accel: bool,
+    pub gyro: bool,
+    pub mag: bool,
+    /// Accelerometer calibration for a device that stays flat
+    pub planar: bool,
+    /// Calibration while the device rests on a table
+    pub on_table: bool,
+}
+
+impl CalibrationConfig {
+    pub fn set_request(&self) -> CommandRequest {
+        CommandRequest::new(
+            Command::MeCalibration,
+            [
+                self.accel as u8,
+                self.gyro as u8,
+                self.mag as u8,
+                ME_CALIBRATION_CONFIGURE,
+                self.planar as u8,
+                self.on_table as u8,
+                0,
+                0,
+                0,
+            ],
+        )
+    }
+
+    pub fn get_request() -> CommandRequest {
+        let mut params = [0u8; 9];
+        params[3] = ME_CALIBRATION_GET;
+        CommandRequest::new(Command::MeCalibration, params)
+    }
+
+    /// Reads the settings from R1..R5 of either response.
+    pub fn from_response(response: &CommandResponse) -> Self {
+        let f = &response.fields;
+        CalibrationConfig {
+            accel: f[1] != 0,
+            gyro: f[2] != 0,
+            mag: f[3] != 0,
+            planar: f[4] != 0,
+            on_table: f[5] != 0,
+        }
+    }
+}
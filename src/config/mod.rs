@@ -1,11 +1,48 @@
 pub const DEFAULT_REPORT_INTERVAL: u32 = 10000;
+pub const INTERRUPT_TIMEOUT_NS: u32 = 300_000_000;
+pub const I2C_INTERRUPT_POLL_NS: u32 = 1_000;
+pub const UART_BYTE_SPACING_US: u32 = 100;
+pub const PACKET_CAPACITY: usize = 512;
+pub const RESET_COMPLETE_TIMEOUT_MS: u32 = 1000;
+pub const RESPONSE_TIMEOUT_MS: u32 = 300;
+pub const INPUT_TIMEOUT_MS: u32 = 10;
+/// Idle time between reads of a wait loop, and the least one pass counts for
+pub const WAIT_STEP_NS: u32 = 1_000_000;
+
+/// How long the driver waits on the hub, in milliseconds. Waiting on INT
+/// inside a read counts towards them too.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Timeouts {
+    /// Control channel responses: product ID, features, FRS records
+    pub response_ms: u32,
+    /// Reset complete after a soft or hard reset
+    pub reset_ms: u32,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Timeouts {
+            response_ms: RESPONSE_TIMEOUT_MS,
+            reset_ms: RESET_COMPLETE_TIMEOUT_MS,
+        }
+    }
+}
+#[allow(dead_code)]
 pub const QUAT_READ_ATTEMPTS: u8 = 10;
+#[allow(dead_code)]
 pub const PACKET_READ_ATTEMPTS: u8 = 20;
+#[allow(dead_code)]
 pub const FEATURE_ENABLE_ATTEMPTS: u8 = 5;
+#[allow(dead_code)]
 pub const DEFAULT_ATTEMPTS: u8 = 10;
 
 pub const GYRO_SCALAR_Q_POINT: u8 = 9;
 pub const ACCEL_SCALAR_Q_POINT: u8 = 8;
 pub const QUAT_SCALAR_Q_POINT: u8 = 14;
+#[allow(dead_code)]
 pub const GEO_QUAT_SCALAR_Q_POINT: u8 = 12;
 pub const MAG_SCALAR_Q_POINT: u8 = 4;
+
+pub const RVC_ANGLE_SCALE: f32 = 0.01;
+pub const STANDARD_GRAVITY: f32 = 9.80665;
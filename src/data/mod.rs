@@ -1,51 +1,57 @@
-use defmt::{debug, info};
-use heapless::{Vec, vec};
+use heapless::Vec;
 
-pub struct Packet {
+pub use crate::config::PACKET_CAPACITY;
+
+/// One SHTP transfer. `N` bounds the cargo (plus the I2C/SPI spacer) held in
+/// memory and should cover the `max_cargo_read` the hub advertises; longer
+/// transfers are cut short and finished by continuations.
+pub struct Packet<const N: usize = PACKET_CAPACITY> {
     length: u16,
     channel: u8,
     seq_num: u8,
     spacer: bool,
-    header: heapless::Vec<u8, 4>,
-    data: heapless::Vec<u8, 8191>,
+    continuation: bool,
+    header: [u8; 4],
+    data: heapless::Vec<u8, N>,
 }
 
-impl Packet {
+impl<const N: usize> Packet<N> {
     pub fn new(spacer: bool) -> Self {
         Packet {
             length: 0,
             channel: 6,
             seq_num: 0,
             spacer,
-            header: Vec::from_array([0; 4]),
+            continuation: false,
+            header: [0; 4],
             data: Vec::new(),
         }
     }
 
-    pub fn from_buf(buf: &[u8], spacer: bool) -> Self {
-        // debug!("PACKET GEN IS starteed");
-        // let len = buf.len();
-        // debug!("{}", len);
+    /// Builds a packet from a raw transfer, SHTP header first.
+    pub fn from_buf(buf: &[u8], spacer: bool) -> Result<Self, PacketError> {
+        let (header, data) = buf.split_first_chunk::<4>().ok_or(PacketError::Truncated)?;
         let mut temp = Packet {
             length: 0,
             channel: 6,
             seq_num: 0,
             spacer,
-            header: Vec::from_slice(&buf[..4]).expect("Packet creation error"),
-            data: Vec::from_slice(buf.get(4..).unwrap_or(&[])).expect("Packet creation error"),
+            continuation: false,
+            header: *header,
+            data: Vec::from_slice(data).map_err(|_| PacketError::TooLarge)?,
         };
-        // info!("PACKET GEN IS FINE");
         temp.process_header(false);
-        temp
+        Ok(temp)
     }
 
-    pub fn from_header(header: &[u8], spacer: bool) -> Self {
+    pub fn from_header(header: &[u8; 4], spacer: bool) -> Self {
         let mut temp = Packet {
             length: 0,
             channel: 6,
             seq_num: 0,
             spacer,
-            header: Vec::from_slice(&header[..4]).expect("Packet creation error"),
+            continuation: false,
+            header: *header,
             data: Vec::new(),
         };
         // info!("PACKET GEN IS FINE");
@@ -61,11 +67,12 @@ impl Packet {
     ) -> Result<Self, crate::data::PacketError> {
         let mut temp = Packet {
             length: 0,
-            channel: channel,
+            channel,
             seq_num: 0,
             spacer,
-            header: Vec::from_array([0; 4]),
-            data: Vec::from_slice(buf).expect("Packet creation error"),
+            continuation: false,
+            header: [0; 4],
+            data: Vec::from_slice(buf).map_err(|_| PacketError::TooLarge)?,
         };
         temp.generate_header(buf, Some(seq_num));
         if channel < 6 {
@@ -75,8 +82,27 @@ impl Packet {
         }
     }
 
+    /// Builds a packet from what the hub clocked out during a write: its
+    /// header and as much of its cargo as the write was long.
+    pub fn from_parts(header: [u8; 4], data: Vec<u8, N>) -> Self {
+        let mut temp = Packet {
+            length: 0,
+            channel: 6,
+            seq_num: 0,
+            spacer: false,
+            continuation: false,
+            header,
+            data,
+        };
+        temp.process_header(false);
+        // Anything past the hub's own frame is filler
+        temp.data.truncate(temp.data_length() as usize);
+        temp
+    }
+
     fn generate_header(&mut self, data_buf: &[u8], seq_num: Option<u8>) {
         let length = data_buf.len() as u16 + 4;
+        self.length = length;
         let length_slice: [u8; 2] = length.to_le_bytes();
         self.header[0] = length_slice[0];
         self.header[1] = length_slice[1];
@@ -95,29 +121,27 @@ impl Packet {
 
     pub fn process_header(&mut self, resize: bool) {
         // info!("HEADER: {:#X}", self.header.as_slice());
-        self.calculate_length();
+        self.continuation = self.calculate_length().is_err();
         // info!("LENGTH DATA: {}", self.length);
-        if resize && !self.spacer {
-            self.data.resize(self.data_length() as usize, 0);
-        } else if resize && self.spacer {
-            self.data
-                .resize(
-                    (if self.data_length() < u16::MAX - 4 {
-                        self.data_length() + 4
-                    } else {
-                        self.data_length()
-                    }) as usize,
-                    0,
-                )
-                .ok();
+        if resize {
+            let spacer = if self.spacer { 4 } else { 0 };
+            let length = (self.data_length() as usize + spacer).min(self.data.capacity());
+            self.data.resize(length, 0).ok();
         }
         self.channel = u8::from_le_bytes([self.header[2]]);
         self.seq_num = u8::from_le_bytes([self.header[3]]);
     }
 
+    /// Caps how many bytes the next read fills, spacer included. Whatever
+    /// is left over is sent by the hub as a continuation.
+    pub fn limit_transfer(&mut self, max_read: usize) {
+        if self.data.len() > max_read {
+            self.data.truncate(max_read);
+        }
+    }
+
     fn calculate_length(&mut self) -> Result<(), PacketError> {
-        let length_slice: [u8; 2] = self.header[..2].try_into().expect("Failed to slice header");
-        let value = u16::from_le_bytes(length_slice);
+        let value = u16::from_le_bytes([self.header[0], self.header[1]]);
 
         self.length = value & 0x7FFF;
 
@@ -134,7 +158,26 @@ impl Packet {
     }
 
     pub fn data_length(&self) -> u16 {
-        if self.length > 4 { self.length - 4 } else { 0 }
+        self.length.saturating_sub(4)
+    }
+
+    /// Cargo bytes actually held, which is less than
+    /// [`data_length`](Self::data_length) for the first fragment of a split
+    /// transfer.
+    pub fn cargo_length(&self) -> usize {
+        if self.spacer {
+            self.data.len().saturating_sub(4)
+        } else {
+            self.data.len()
+        }
+    }
+
+    pub fn is_continuation(&self) -> bool {
+        self.continuation
+    }
+
+    pub fn is_complete(&self) -> bool {
+        !self.continuation && self.cargo_length() >= self.data_length() as usize
     }
 
     pub fn get_data_report(&mut self) -> (u16, &mut [u8]) {
@@ -149,12 +192,18 @@ impl Packet {
         }
     }
 
-    pub fn full_packet(&mut self) -> Vec<u8, 32767> {
-        let mut temp: Vec<u8, 32767> =
-            Vec::from_slice(self.as_mut_header()).expect("Failed to create full packet");
-        temp.extend_from_slice(self.as_mut_data(false))
-            .expect("Failed to attach data slice to full packet");
-        temp
+    pub fn header(&self) -> &[u8; 4] {
+        &self.header
+    }
+
+    /// Cargo without the spacer, ready to be written after
+    /// [`header`](Self::header).
+    pub fn cargo(&self) -> &[u8] {
+        if self.spacer {
+            self.data.get(4..).unwrap_or(&[])
+        } else {
+            &self.data
+        }
     }
 
     pub fn channel(&self) -> u8 {
@@ -167,19 +216,54 @@ impl Packet {
 
     pub fn report_id(&self) -> u8 {
         if self.data_length() > 0 && !self.spacer {
-            self.data.get(0).unwrap_or(&0).clone()
-        } else if self.spacer && self.data_length() > 4 {
-            self.data.get(4).unwrap_or(&0).clone()
+            *self.data.first().unwrap_or(&0)
+        } else if self.spacer && self.data_length() > 0 {
+            *self.data.get(4).unwrap_or(&0)
         } else {
             0
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum PacketError {
     HalfPacket,
     InvalidChannel,
+    MissingFragment,
+    TooLarge,
+    /// Fewer bytes than an SHTP header
+    Truncated,
+}
+
+/// Inbound sequence number bookkeeping for one channel. The hub numbers
+/// every transfer on a channel, so a jump means transfers were lost and a
+/// repeat means one was delivered twice.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SequenceStats {
+    pub last: Option<u8>,
+    pub received: u32,
+    /// Transfers missing between consecutive sequence numbers
+    pub gaps: u32,
+    pub duplicates: u32,
+}
+
+impl SequenceStats {
+    pub fn record(&mut self, seq_num: u8) {
+        self.received = self.received.wrapping_add(1);
+        if let Some(last) = self.last {
+            let expected = last.wrapping_add(1);
+            if seq_num == last {
+                self.duplicates = self.duplicates.wrapping_add(1);
+            } else if seq_num != expected {
+                self.gaps = self
+                    .gaps
+                    .wrapping_add(seq_num.wrapping_sub(expected) as u32);
+            }
+        }
+        self.last = Some(seq_num);
+    }
 }
 
 pub struct VarBuf {
@@ -187,6 +271,12 @@ pub struct VarBuf {
     valid_data: usize,
 }
 
+impl Default for VarBuf {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl VarBuf {
     pub fn new() -> Self {
         VarBuf {
@@ -205,6 +295,7 @@ impl VarBuf {
         &self.buf[..self.valid_data]
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn as_mut(&mut self) -> &mut [u8] {
         &mut self.buf[..self.valid_data]
     }
@@ -214,7 +305,7 @@ impl VarBuf {
     }
 
     pub fn clone_buf(self) -> [u8; 256] {
-        self.buf.clone()
+        self.buf
     }
 }
 
@@ -229,17 +320,12 @@ pub struct ProductId {
 impl ProductId {
     pub fn new(command_buf: &[u8]) -> Self {
         if command_buf.len() >= 15 {
-            let sw_part_num_buf: [u8; 4] =
-                command_buf[4..8].try_into().expect("Failed to parse buf");
-            let sw_build_num_buf: [u8; 4] =
-                command_buf[8..12].try_into().expect("Failed to parse buf");
-            let patch_num_buf: [u8; 2] =
-                command_buf[12..14].try_into().expect("Failed to parse buf");
+            let b = command_buf;
             ProductId {
-                sw_ver: (command_buf[2], command_buf[3]),
-                sw_part_num: u32::from_le_bytes(sw_part_num_buf),
-                sw_build_num: u32::from_le_bytes(sw_build_num_buf),
-                patch_num: u16::from_le_bytes(patch_num_buf),
+                sw_ver: (b[2], b[3]),
+                sw_part_num: u32::from_le_bytes([b[4], b[5], b[6], b[7]]),
+                sw_build_num: u32::from_le_bytes([b[8], b[9], b[10], b[11]]),
+                patch_num: u16::from_le_bytes([b[12], b[13]]),
             }
         } else {
             ProductId::default()
@@ -1,5 +1,7 @@
 use core::convert::Infallible;
 
+use crate::data::PacketError;
+
 pub enum CSPinError {
     PinError,
 }
@@ -9,3 +11,34 @@ impl From<Infallible> for CSPinError {
         CSPinError::PinError
     }
 }
+
+#[derive(Debug)]
+pub enum I2cError<E> {
+    I2c(E),
+    /// Reading the INT line or driving the reset line failed
+    Pin,
+}
+
+#[derive(Debug)]
+pub enum SpiError<E> {
+    Spi(E),
+    Pin,
+    /// The hub didn't pull INT low after WAKE or a reset
+    Timeout,
+}
+
+#[derive(Debug)]
+pub enum UartError<E> {
+    Uart(E),
+    Frame,
+    /// A frame the packet buffer can't hold. It is skipped, the next read
+    /// starts at the frame after it.
+    Packet(PacketError),
+}
+
+#[derive(Debug)]
+pub enum CaptureError<E> {
+    Io(E),
+    /// Record header or frame length doesn't make sense
+    Format,
+}
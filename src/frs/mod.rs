@@ -1,10 +1,11 @@
 use crate::{
-    READ, SensorError, WRITE,
-    data::Packet,
+    SensorError,
     register::{FRSConfiguration, Register, SH2Read, SH2Write},
 };
 
-#[derive(Copy, Clone, Debug, defmt::Format)]
+/// Status of an FRS read response.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum FRSStatus {
     NoError,
     UnrecognizedFRSType,
@@ -34,31 +35,25 @@ fn process_status(status: u8) -> FRSStatus {
     }
 }
 
-#[derive(Debug, defmt::Format)]
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct FRSDataRead {
     request_type: FRSConfiguration,
-    length: Option<u8>,
-    offset: Option<u16>,
-    status: Option<FRSStatus>,
-    data_0: Option<u32>,
-    data_1: Option<u32>,
+    /// Words of the record seen so far
+    length: usize,
 }
 
 impl FRSDataRead {
     pub fn new(request: FRSConfiguration) -> Self {
         FRSDataRead {
             request_type: request,
-            length: None,
-            offset: None,
-            status: None,
-            data_0: None,
-            data_1: None,
+            length: 0,
         }
     }
 
-    pub fn generate_read_request(&self) -> Result<[u8; 8], crate::SensorError> {
+    pub fn generate_read_request(&self) -> [u8; 8] {
         let addr_bytes: &[u8; 2] = &self.request_type.addr();
-        Ok([
+        [
             Register::Write(SH2Write::FrsReadRequest).addr(),
             0,
             0,
@@ -67,46 +62,53 @@ impl FRSDataRead {
             addr_bytes[1],
             0,
             0,
-        ])
+        ]
     }
 
-    pub fn process_read_response(&mut self, data: &[u8]) -> Result<(), SensorError> {
-        if data.len() >= 16 && data[0] == Register::Read(SH2Read::FrsReadResponse).addr() {
-            self.length = Some(data[1] >> 4);
-            self.status = Some(process_status(data[1]));
-            self.offset = Some(u16::from_le_bytes(
-                data[2..4].try_into().expect("Failed to slice"),
-            ));
-            if let Some(length) = self.length
-                && length > 0
-            {
-                self.data_0 = Some(u32::from_le_bytes(
-                    data[4..8].try_into().expect("Failed to slice."),
-                ));
-            }
-            if let Some(length) = self.length
-                && length > 1
-            {
-                self.data_1 = Some(u32::from_le_bytes(
-                    data[8..12].try_into().expect("Failed to slice."),
-                ));
-            }
-            let request = u16::from_le_bytes(data[12..14].try_into().expect("Failed to slice."));
-            let request =
-                FRSConfiguration::try_from(request).expect("Failed to find configuration");
+    /// Whether `data` is a read response for the requested record.
+    pub fn answers(&self, data: &[u8]) -> bool {
+        data.len() >= 16
+            && data[0] == Register::Read(SH2Read::FrsReadResponse).addr()
+            && u16::from_le_bytes([data[12], data[13]]) == self.request_type as u16
+    }
 
-            if request == self.request_type {
-                Ok(())
-            } else {
-                Err(SensorError::Unimplemented)
+    /// Stores the words carried by one response at their offset in
+    /// `words`, dropping those past its end. Returns whether the record is
+    /// complete.
+    pub fn process_read_response<E>(
+        &mut self,
+        data: &[u8],
+        words: &mut [u32],
+    ) -> Result<bool, SensorError<E>> {
+        if !self.answers(data) {
+            return Err(SensorError::InvalidLength);
+        }
+
+        let length = (data[1] >> 4) as usize;
+        let offset = u16::from_le_bytes([data[2], data[3]]) as usize;
+        for (index, word) in data[4..12].chunks_exact(4).take(length).enumerate() {
+            if let Some(slot) = words.get_mut(offset + index) {
+                *slot = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
             }
-        } else {
-            Err(SensorError::InvalidLength)
         }
+        self.length = self.length.max(offset + length.min(2));
+
+        match process_status(data[1]) {
+            FRSStatus::NoError => Ok(false),
+            FRSStatus::ReadRecordCompleted | FRSStatus::RecordEmpty => Ok(true),
+            status => Err(SensorError::Frs(status)),
+        }
+    }
+
+    /// Words in the record, once it is complete.
+    pub fn word_count(&self) -> usize {
+        self.length
     }
 }
 
-#[derive(Debug, defmt::Format)]
+#[allow(dead_code)]
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct FRSDataWrite {
     request_type: FRSConfiguration,
     data_ready: bool,
@@ -116,6 +118,7 @@ pub struct FRSDataWrite {
     data_1: Option<u32>,
 }
 
+#[allow(dead_code)]
 impl FRSDataWrite {
     pub fn new(request: FRSConfiguration, data_0: Option<u32>, data_1: Option<u32>) -> Self {
         let mut data_length: u8 = 0;
@@ -1,401 +1,738 @@
 #![no_std]
 
 use embedded_hal::delay::DelayNs;
-use embedded_hal::digital::OutputPin;
+use embedded_hal::digital::{InputPin, OutputPin};
 use embedded_hal::i2c::I2c;
-use embedded_hal::{self as hal};
+use embedded_hal::spi::SpiDevice;
+use embedded_io::{Read, ReadReady, Write};
 use heapless::Vec;
-use panic_probe as _;
 
-use hal::digital::InputPin;
-use hal::spi::*;
-
-use defmt::*;
-
-use crate::config::DEFAULT_REPORT_INTERVAL;
-use crate::data::{Packet, ProductId, VarBuf};
+use crate::advertisement::{Advertisement, ChannelMap};
+use crate::clock::Deadline;
+use crate::command::{
+    CalibrationConfig, Command, CommandRequest, CommandResponse, Dcd, ERROR_REPORT_CAPACITY,
+    ErrorRecord, SensorCounts, Tare, TareAxes, TareBasis,
+};
+use crate::config::{INPUT_TIMEOUT_MS, INTERRUPT_TIMEOUT_NS, WAIT_STEP_NS};
+use crate::data::{PACKET_CAPACITY, Packet, PacketError, SequenceStats};
 use crate::frs::FRSDataRead;
-use crate::parsing::{get_feature_dependencies, get_report_length};
 use crate::register::*;
-use crate::sensors::Sensors;
+use crate::state::{DriverState, WaitStep};
+use crate::transport::{I2cTransport, NoInterrupt, SpiTransport, Transport, UartTransport};
+
+#[macro_use]
+mod fmt;
 
+pub mod advertisement;
+pub mod asynch;
+pub mod capture;
+mod clock;
+pub mod command;
 mod config;
 pub mod data;
 pub mod error;
 mod frs;
 mod parsing;
+mod reassembly;
 pub mod register;
+pub mod rvc;
 mod sensors;
+#[cfg(feature = "sim")]
+pub mod sim;
+mod state;
+pub mod transport;
 
-const WRITE: bool = true;
-const READ: bool = false;
-const TIMEOUT: u32 = 2000000;
+pub use crate::config::Timeouts;
+pub use crate::frs::FRSStatus;
 
 // BAUD RATE MUST BE 100000 HZ AT 3MHZ SPI FREQUENCY!!!!!!
-pub struct BNO08x<I2C, D> {
-    address: u8,
-    i2c: I2C,
+pub struct BNO08x<T, D, const N: usize = PACKET_CAPACITY> {
+    transport: T,
     delay: D,
-    seq_num_w: [u8; 6],
-    seq_num_r: [u8; 6],
-    sensors: Sensors,
-    features: Vec<ReportId, 42>,
+    state: DriverState<N>,
 }
 
-impl<I2C, D> BNO08x<I2C, D>
+impl<I2C, D> BNO08x<I2cTransport<I2C>, D>
 where
     I2C: I2c,
     D: DelayNs,
 {
     pub fn new(i2c: I2C, delay: D, default_addr: bool) -> Self {
+        let address = if default_addr {
+            I2CAddress::Default as u8
+        } else {
+            I2CAddress::Alternate as u8
+        };
+        Self::new_with_address(i2c, delay, address)
+    }
+
+    /// Talks to the hub at any 7-bit `address`. To share the bus with other
+    /// hubs or sensors, hand each one its own device from `embedded-hal-bus`
+    /// (e.g. `RefCellDevice` or `CriticalSectionDevice`).
+    pub fn new_with_address(i2c: I2C, delay: D, address: u8) -> Self {
+        Self::from_transport(I2cTransport::new(i2c, address), delay)
+    }
+}
+
+impl<I2C, IP, D> BNO08x<I2cTransport<I2C, IP>, D>
+where
+    I2C: I2c,
+    IP: InputPin,
+    D: DelayNs,
+{
+    /// Like [`new`](BNO08x::new), but only reads once the hub pulls INT low
+    /// instead of polling the bus.
+    pub fn new_with_interrupt(i2c: I2C, interrupt: IP, delay: D, default_addr: bool) -> Self {
+        let address = if default_addr {
+            I2CAddress::Default as u8
+        } else {
+            I2CAddress::Alternate as u8
+        };
+        Self::from_transport(I2cTransport::with_interrupt(i2c, address, interrupt), delay)
+    }
+}
+
+impl<I2C, RP, D> BNO08x<I2cTransport<I2C, NoInterrupt, RP>, D>
+where
+    I2C: I2c,
+    RP: OutputPin,
+    D: DelayNs,
+{
+    /// Like [`new`](BNO08x::new), but owns the hub's NRST line so
+    /// [`hard_reset_device`](BNO08x::hard_reset_device) can recover it.
+    pub fn new_with_reset(i2c: I2C, reset: RP, delay: D, default_addr: bool) -> Self {
+        let address = if default_addr {
+            I2CAddress::Default as u8
+        } else {
+            I2CAddress::Alternate as u8
+        };
+        Self::from_transport(I2cTransport::new(i2c, address).with_reset(reset), delay)
+    }
+}
+
+impl<SPI, IP, WP, RP, D> BNO08x<SpiTransport<SPI, IP, WP, RP>, D>
+where
+    SPI: SpiDevice,
+    IP: InputPin,
+    WP: OutputPin,
+    RP: OutputPin,
+    D: DelayNs,
+{
+    pub fn new_spi(spi: SPI, interrupt: IP, wake: WP, reset: RP, delay: D) -> Self {
+        Self::from_transport(SpiTransport::new(spi, interrupt, wake, reset), delay)
+    }
+}
+
+impl<U, D> BNO08x<UartTransport<U>, D>
+where
+    U: Read + ReadReady + Write,
+    D: DelayNs,
+{
+    pub fn new_uart(uart: U, delay: D) -> Self {
+        Self::from_transport(UartTransport::new(uart), delay)
+    }
+}
+
+impl<T, D, const N: usize> BNO08x<T, D, N>
+where
+    T: Transport,
+    D: DelayNs,
+{
+    /// Packet buffers hold `N` bytes each, pick it with
+    /// `BNO08x::<_, _, N>::from_transport` to trade RAM for cargo size.
+    pub fn from_transport(transport: T, delay: D) -> Self {
         BNO08x {
-            address: if default_addr {
-                I2CAddress::Default as u8
-            } else {
-                I2CAddress::Alternate as u8
-            },
-            i2c,
+            transport,
             delay,
-            seq_num_w: [0; 6],
-            seq_num_r: [0; 6],
-            sensors: Sensors::new(),
-            features: Vec::new(),
+            state: DriverState::new(),
         }
     }
 
-    fn read_header(&mut self) -> Packet {
-        let mut header = [0u8; 4];
-        self.i2c.read(self.address, &mut header).ok();
+    pub fn release(self) -> (T, D) {
+        (self.transport, self.delay)
+    }
+
+    /// Resets the hub over the executable channel and waits for it to
+    /// report back. Every report is disabled afterwards, and the driver
+    /// forgets them too: later hard or unsolicited resets won't restore
+    /// them.
+    pub fn soft_reset_device(&mut self) -> Result<(), SensorError<T::Error>> {
+        self.drain()?;
+        self.send_exec_command(ExecCommand::Reset)?;
+        // The advertisement is picked up by read_packet
+        self.state.on_soft_reset();
+
+        self.wait_for_reset_complete()
+    }
+
+    /// Pulses the hub's reset line and waits for it to come back, then
+    /// enables the features that were on before.
+    pub fn hard_reset_device(&mut self) -> Result<(), SensorError<T::Error>> {
+        info!("BNO08x Device Resetting");
+        if !self
+            .transport
+            .hard_reset(&mut self.delay)
+            .map_err(SensorError::Bus)?
+        {
+            warn!("Transport has no reset line");
+            return Err(SensorError::Unimplemented);
+        }
+        self.state.on_reset();
 
-        Packet::from_header(&header, true)
+        self.wait_for_reset_complete()?;
+        info!("BNO08x Device Reset");
+        self.restore_features()
     }
 
-    pub fn soft_reset_device(&mut self) {
-        let mut write =
-            Packet::from_data_buf(&[0x01], 1, self.seq_num_w[1], false).expect("PACK GEN ERROR");
-        self.increment_seq_num(WRITE, 1, None);
+    /// Puts the hub into sleep. Enabled reports stop until
+    /// [`wake_device`](Self::wake_device) is called.
+    pub fn sleep_device(&mut self) -> Result<(), SensorError<T::Error>> {
+        self.send_exec_command(ExecCommand::Sleep)
+    }
 
-        self.i2c
-            .write(self.address, write.full_packet().as_slice())
-            .ok();
+    /// Sends the executable channel On command, resuming enabled reports
+    /// after [`sleep_device`](Self::sleep_device).
+    pub fn wake_device(&mut self) -> Result<(), SensorError<T::Error>> {
+        self.send_exec_command(ExecCommand::On)
+    }
 
-        self.delay.delay_ms(500);
-        self.i2c
-            .write(self.address, write.full_packet().as_slice())
-            .ok();
-        self.delay.delay_ms(500);
+    fn send_exec_command(&mut self, command: ExecCommand) -> Result<(), SensorError<T::Error>> {
+        let channel = self.state.channels.executable;
+        self.send_packet_from_data(channel, &[command as u8])
+    }
 
-        for _ in 0..3 {
-            let mut packet = self.read_packet();
-            // info!("AFTER REST: {}",  packet.full_packet().as_slice())
+    /// Sends every remembered feature configuration to the hub again.
+    fn restore_features(&mut self) -> Result<(), SensorError<T::Error>> {
+        for index in 0..self.state.feature_config.len() {
+            let (feature_id, interval, sens_specific) = self.state.feature_config[index];
+            self.enable_features(feature_id, interval, sens_specific)?;
         }
+        Ok(())
     }
 
-    pub fn read_packet(&mut self) -> Packet {
-        let mut out = self.read_header();
-        out.process_header(true);
-        self.delay.delay_ms(5);
-        // info!("R PACK LENGTH: {}", out.packet_length());
-        // info!("R CHANNEL NUM: {}", 0);
-        // info!("R SEQ NUM: {}", out.seq_num());
-        // info!(
-        // "R CHANNEL {} HAS {} BYTES AVAILABLE",
-        // out.channel(),
-        // out.data_length()
-        // );
-        self.seq_num_r[0 as usize] = out.seq_num();
-
-        if out.packet_length() == 0 {
-            // info!("NO PACKET AVAILABLE");
+    fn wait_for_reset_complete(&mut self) -> Result<(), SensorError<T::Error>> {
+        let timeout_ms = self.state.timeouts.reset_ms;
+        let mut deadline = Deadline::from_ms(timeout_ms);
+        loop {
+            // The hub may not answer on the bus until it has booted, so
+            // errors only count once the timeout runs out
+            if let Ok(packet) = self.read_packet_until(&mut deadline)
+                && self.state.is_reset_complete(&packet)
+            {
+                debug!("Reset complete");
+                return Ok(());
+            }
+            self.idle(&mut deadline);
+            if deadline.end_pass() {
+                break;
+            }
         }
-        self.i2c.read(self.address, out.as_mut_data(true)).ok();
 
-        out
+        Err(self.state.reset_timeout())
+    }
+
+    /// Takes whatever the hub has queued without waiting on INT, so
+    /// nothing sent before a reset is mistaken for its answer.
+    fn drain(&mut self) -> Result<(), SensorError<T::Error>> {
+        let mut deadline = Deadline::from_ms(self.state.timeouts.response_ms);
+        loop {
+            match self.read_packet_until(&mut Deadline::new(0)) {
+                Ok(packet) if packet.packet_length() == 0 => return Ok(()),
+                Err(SensorError::Bus(e)) => return Err(SensorError::Bus(e)),
+                _ => {}
+            }
+            // A hub streaming reports never runs dry
+            if deadline.end_pass() {
+                return Ok(());
+            }
+        }
     }
 
-    pub fn send_packet_from_data(&mut self, channel: u8, data: &[u8]) {
-        let seq = self.increment_seq_num(WRITE, channel, None);
-        let mut write = Packet::from_data_buf(data, channel, seq, false).expect("PacketGen failed");
+    /// Reads the next logical packet, joining continuation fragments.
+    pub fn read_packet(&mut self) -> Result<Packet<N>, SensorError<T::Error>> {
+        self.read_packet_until(&mut Deadline::new(INTERRUPT_TIMEOUT_NS.into()))
+    }
 
-        debug!("Packet Created");
-        self.i2c
-            .write(self.address, write.full_packet().as_slice())
-            .ok();
+    /// Reads the next logical packet, waiting on INT only as long as
+    /// `deadline` allows.
+    fn read_packet_until(
+        &mut self,
+        deadline: &mut Deadline,
+    ) -> Result<Packet<N>, SensorError<T::Error>> {
+        if let Some(out) = self.state.take_complete() {
+            return Ok(out);
+        }
+
+        loop {
+            let timeout_ns = deadline.remaining_ns();
+            let out = self
+                .transport
+                .read_packet(&mut deadline.stopwatch(&mut self.delay), timeout_ns)
+                .map_err(SensorError::Bus)?;
+            let out = self.state.receive(out);
+            self.hand_over_max_read();
+            if let Some(out) = out.map_err(SensorError::Packet)? {
+                return Ok(out);
+            }
+        }
+    }
+
+    /// Gives the hub a moment between reads that brought nothing.
+    fn idle(&mut self, deadline: &mut Deadline) {
+        let ns = deadline.remaining_ns().min(WAIT_STEP_NS);
+        deadline.stopwatch(&mut self.delay).delay_ns(ns);
+    }
+
+    /// Passes the read limit from a new advertisement on to the transport.
+    fn hand_over_max_read(&mut self) {
+        if let Some(max_read) = self.state.max_read.take() {
+            self.transport.set_max_read(max_read);
+        }
+    }
+
+    /// The hub's last advertisement, if one has been seen since startup.
+    pub fn advertisement(&self) -> Option<&Advertisement> {
+        self.state.advertisement.as_ref()
+    }
+
+    pub fn channels(&self) -> ChannelMap {
+        self.state.channels
+    }
+
+    /// Inbound sequence number counters for `channel`.
+    pub fn sequence_stats(&self, channel: u8) -> Option<SequenceStats> {
+        self.state.seq_num_r.get(channel as usize).copied()
+    }
+
+    pub fn clear_sequence_stats(&mut self) {
+        self.state.seq_num_r = [SequenceStats::default(); 6];
+    }
+
+    /// Whether the hub reset on its own since the last call. The driver
+    /// enables the features again by itself, but anything else the hub
+    /// forgot (tare, calibration settings) is up to the application.
+    pub fn take_reset_event(&mut self) -> bool {
+        core::mem::take(&mut self.state.reset_event)
+    }
+
+    pub fn timeouts(&self) -> Timeouts {
+        self.state.timeouts
+    }
+
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) {
+        self.state.timeouts = timeouts;
+    }
+
+    pub fn send_packet_from_data(
+        &mut self,
+        channel: u8,
+        data: &[u8],
+    ) -> Result<(), SensorError<T::Error>> {
+        let header = self
+            .state
+            .write_header(channel, data.len())
+            .map_err(SensorError::Packet)?;
+
+        let received = self
+            .transport
+            .write_packet(&header, data, &mut self.delay)
+            .map_err(SensorError::Bus)?;
+        self.state.receive_during_write(received);
+        self.hand_over_max_read();
         debug!("PACKET SENT");
+        Ok(())
     }
 
-    pub fn send_full_packet(&mut self, channel: u8, mut packet: Packet) {
-        let seq = self.increment_seq_num(WRITE, channel, None);
+    pub fn send_full_packet(
+        &mut self,
+        channel: u8,
+        packet: Packet<N>,
+    ) -> Result<(), SensorError<T::Error>> {
+        self.state.increment_seq_num(channel);
 
         debug!("Packet Created");
-        self.i2c
-            .write(self.address, packet.full_packet().as_slice())
-            .ok();
+        let received = self
+            .transport
+            .write_packet(packet.header(), packet.cargo(), &mut self.delay)
+            .map_err(SensorError::Bus)?;
+        self.state.receive_during_write(received);
+        self.hand_over_max_read();
         debug!("PACKET SENT");
+        Ok(())
     }
 
+    /// Reads until a packet on `channel` (with `report_id`, if given)
+    /// arrives, giving up with [`SensorError::Timeout`] after `timeout_ms`.
+    /// Bus errors end the wait straight away.
     fn wait_for_packet(
         &mut self,
         channel: u8,
         report_id: Option<SH2Read>,
-        max: Option<u8>,
-    ) -> Result<Packet, SensorError> {
-        let max_attempts = max.unwrap_or(0);
-
-        let mut out = Packet::new(true);
-        if max_attempts > 0 {
-            let mut retries = 0;
-            if let Some(report_id) = report_id {
-                while !(out.channel() == channel
-                    && out.report_id() == Register::Read(report_id).addr())
-                    && retries < max_attempts
-                {
-                    retries += 1;
-                    out = self.read_packet();
-                }
-                if retries == max_attempts
-                    && out.channel() != channel
-                    && out.report_id() != Register::Read(report_id).addr()
-                {
-                    warn!(
-                        "Failed to fetch packet in time with report id {}",
-                        Register::Read(report_id).addr()
-                    );
-                    return Err(SensorError::PacketRetrievalFailed);
-                }
-            } else {
-                while out.channel() != channel && retries < max_attempts {
-                    retries += 1;
-                    out = self.read_packet();
-                }
-                if retries == max_attempts && out.channel() != channel {
-                    return Err(SensorError::PacketRetrievalFailed);
-                }
+        timeout_ms: u32,
+    ) -> Result<Packet<N>, SensorError<T::Error>> {
+        self.wait_for_packet_where(channel, report_id, timeout_ms, |_| true)
+    }
+
+    /// Like [`wait_for_packet`](Self::wait_for_packet), but also skips
+    /// packets `accept` turns down.
+    fn wait_for_packet_where(
+        &mut self,
+        channel: u8,
+        report_id: Option<SH2Read>,
+        timeout_ms: u32,
+        accept: impl Fn(&Packet<N>) -> bool,
+    ) -> Result<Packet<N>, SensorError<T::Error>> {
+        let mut deadline = Deadline::from_ms(timeout_ms);
+        loop {
+            let read = self.read_packet_until(&mut deadline);
+            match state::wait_step(read, channel, report_id, &accept) {
+                WaitStep::Done(out) => return out,
+                WaitStep::Skip => {}
+                WaitStep::Idle => self.idle(&mut deadline),
             }
-            // info!("FEATURE REQUEST RESPONSE: {:#X}", out.as_mut_data(true));
-        } else {
-            if let Some(report_id) = report_id {
-                while !(out.channel() == channel
-                    && out.report_id() == Register::Read(report_id).addr())
-                {
-                    out = self.read_packet();
-                }
-            } else {
-                while out.channel() != channel {
-                    out = self.read_packet();
-                }
+            if deadline.end_pass() {
+                return Err(state::timed_out(channel, report_id));
             }
         }
-
-        Ok(out)
     }
 
-    pub fn read_product_id(&mut self) -> Result<bool, SensorError> {
-        debug!("READING P ID");
-        let mut buf_data = [Register::Write(SH2Write::ProductIDRequest).addr(), 0x00];
-        self.send_packet_from_data(2, &buf_data);
-        // self.increment_seq_num(WRITE, 2, None);
-
-        let mut retries = 0;
-        // let max = 3;
+    /// Sends a command request without waiting for an answer. Returns the
+    /// command sequence number it went out with.
+    pub fn send_command(&mut self, request: CommandRequest) -> Result<u8, SensorError<T::Error>> {
+        let (seq, data) = self.state.command_request(request);
+        let control = self.state.channels.control;
+        self.send_packet_from_data(control, &data)?;
+        Ok(seq)
+    }
 
-        let mut out = self.wait_for_packet(2, Some(SH2Read::ProductIDResponse), None);
+    /// Sends a command request and waits for the hub's response to it.
+    pub fn command(
+        &mut self,
+        request: CommandRequest,
+    ) -> Result<CommandResponse, SensorError<T::Error>> {
+        let seq = self.send_command(request)?;
+        self.wait_for_command_response(request.command, seq)
+    }
 
-        if let Ok(mut out) = out {
-            let product_id = ProductId::new(out.as_mut_data(false));
-            // info!("{:?}", product_id.display());
+    /// Waits for the response to request `seq`, dropping any others.
+    pub fn wait_for_command_response(
+        &mut self,
+        command: Command,
+        seq: u8,
+    ) -> Result<CommandResponse, SensorError<T::Error>> {
+        let control = self.state.channels.control;
+        let timeout_ms = self.state.timeouts.response_ms;
+        let packet = self.wait_for_packet_where(
+            control,
+            Some(SH2Read::CommandResponse),
+            timeout_ms,
+            |packet| state::answers(packet, command, seq),
+        )?;
+        state::command_response(&packet)
+    }
 
-            if product_id.display().0 != (0, 0) {
-                Ok(true)
-            } else {
-                Ok(false)
+    /// Fetches the hub's queued errors at `severity` or more severe, 0
+    /// being the most severe. Errors beyond [`ERROR_REPORT_CAPACITY`] are
+    /// dropped.
+    pub fn error_report(
+        &mut self,
+        severity: u8,
+    ) -> Result<Vec<ErrorRecord, ERROR_REPORT_CAPACITY>, SensorError<T::Error>> {
+        let seq = self.send_command(ErrorRecord::request(severity))?;
+        let mut errors = Vec::new();
+        loop {
+            let response = self.wait_for_command_response(Command::ErrorReport, seq)?;
+            if !state::collect_error(&mut errors, &response) {
+                return Ok(errors);
             }
-        } else {
-            Err(SensorError::PacketRetrievalFailed)
         }
     }
 
+    /// Reads the hub's sample counters for `sensor`.
+    pub fn sensor_counts(
+        &mut self,
+        sensor: ReportId,
+    ) -> Result<SensorCounts, SensorError<T::Error>> {
+        let seq = self.send_command(SensorCounts::get_request(sensor))?;
+        let mut counts = SensorCounts::default();
+        // The counts come in two parts
+        for _ in 0..2 {
+            let response = self.wait_for_command_response(Command::Counter, seq)?;
+            counts.update(&response);
+        }
+        Ok(counts)
+    }
+
+    /// Zeroes the hub's sample counters for `sensor`.
+    pub fn clear_sensor_counts(&mut self, sensor: ReportId) -> Result<(), SensorError<T::Error>> {
+        self.send_command(SensorCounts::clear_request(sensor))?;
+        Ok(())
+    }
+
+    /// Zeroes the orientation on `axes` of the `basis` rotation vector.
+    /// Lost at reset unless [`persist_tare`](Self::persist_tare) follows.
+    pub fn tare_now(
+        &mut self,
+        axes: TareAxes,
+        basis: TareBasis,
+    ) -> Result<(), SensorError<T::Error>> {
+        self.send_command(Tare::now(axes, basis))?;
+        Ok(())
+    }
+
+    /// Saves the current tare to flash.
+    pub fn persist_tare(&mut self) -> Result<(), SensorError<T::Error>> {
+        self.send_command(Tare::persist())?;
+        Ok(())
+    }
+
+    /// Sets the rotation applied to all outputs, as a unit quaternion.
+    /// All zeros clears it.
+    pub fn set_reorientation(
+        &mut self,
+        x: f32,
+        y: f32,
+        z: f32,
+        w: f32,
+    ) -> Result<(), SensorError<T::Error>> {
+        self.send_command(Tare::set_reorientation(x, y, z, w))?;
+        Ok(())
+    }
+
+    /// Saves the dynamic calibration data to flash now.
+    pub fn save_dcd(&mut self) -> Result<(), SensorError<T::Error>> {
+        self.checked_command(Dcd::save())?;
+        Ok(())
+    }
+
+    /// Turns the hub's periodic save of the dynamic calibration data on or
+    /// off. With it off, calibration only survives a power cycle through
+    /// [`save_dcd`](Self::save_dcd). The hub sends no response to this
+    /// command (SH2-Reference-Manual 6.4.7), so it isn't confirmed.
+    pub fn set_dcd_autosave(&mut self, enabled: bool) -> Result<(), SensorError<T::Error>> {
+        self.send_command(Dcd::periodic_save(enabled))?;
+        Ok(())
+    }
+
+    /// Turns the background calibration of each sensor on or off.
+    pub fn set_calibration_config(
+        &mut self,
+        config: CalibrationConfig,
+    ) -> Result<(), SensorError<T::Error>> {
+        self.checked_command(config.set_request())?;
+        Ok(())
+    }
+
+    /// Reads which background calibrations are running.
+    pub fn calibration_config(&mut self) -> Result<CalibrationConfig, SensorError<T::Error>> {
+        let response = self.checked_command(CalibrationConfig::get_request())?;
+        Ok(CalibrationConfig::from_response(&response))
+    }
+
+    /// Drops the dynamic calibration data and resets the hub, which then
+    /// starts calibrating from the saved data or defaults. Features that
+    /// were on are enabled again.
+    pub fn clear_dcd_and_reset(&mut self) -> Result<(), SensorError<T::Error>> {
+        self.drain()?;
+        self.send_command(Dcd::clear_and_reset())?;
+        self.state.on_reset();
+
+        self.wait_for_reset_complete()?;
+        self.restore_features()
+    }
+
+    /// Sends a command whose response carries a status in R0.
+    fn checked_command(
+        &mut self,
+        request: CommandRequest,
+    ) -> Result<CommandResponse, SensorError<T::Error>> {
+        let response = self.command(request)?;
+        state::check_status(request.command, response)
+    }
+
+    pub fn read_product_id(&mut self) -> Result<bool, SensorError<T::Error>> {
+        debug!("READING P ID");
+        let timeout_ms = self.state.timeouts.response_ms;
+        let control = self.state.channels.control;
+        self.send_packet_from_data(control, &state::product_id_request())?;
+
+        let out = self.wait_for_packet(control, Some(SH2Read::ProductIDResponse), timeout_ms)?;
+        Ok(state::has_product_id(out))
+    }
+
     pub fn enable_features(
         &mut self,
         feature_id: ReportId,
         interval: Option<u32>,
         sens_specific: Option<u32>,
-    ) {
-        if !self.features.contains(&feature_id) {
-            let mut data_buffer = [0_u8; 17];
-
-            if feature_id == ReportId::PersonalActClassifier {
-                debug!("Unimplemented");
-            } else {
-                data_buffer[0] = 0xFD;
-                data_buffer[1] = feature_id as u8;
-                data_buffer[5..9].copy_from_slice(&u32::to_le_bytes(
-                    interval.unwrap_or(DEFAULT_REPORT_INTERVAL),
-                ));
-                data_buffer[13..17].copy_from_slice(&u32::to_le_bytes(sens_specific.unwrap_or(0)));
-
-                let deps = get_feature_dependencies(feature_id);
-                warn!("ENABLING DEPS: {:?}", deps);
-                if deps.len() > 0 {
-                    for dep in deps {
-                        if !self.features.contains(dep) {
-                            self.enable_features(*dep, None, None);
-                        }
-                    }
-                }
-                warn!("ENABLE FEATURES OUTPUT: {}", &data_buffer);
-                self.send_packet_from_data(2, &data_buffer);
-
-                let mut retries = 0;
-                let mut out = Packet::new(true);
-                if let Ok(out) =
-                    self.wait_for_packet(2, Some(SH2Read::GetFeatureResponse), Some(10))
-                {
-                    self.features.push(feature_id).ok();
-                    warn!("FEATURE ENABLED");
-                } else {
-                    warn!("FAILED TO ENABLE FEATURE {}", feature_id);
-                }
-            }
+    ) -> Result<(), SensorError<T::Error>> {
+        let requests = self
+            .state
+            .feature_requests(feature_id, interval, sens_specific)?;
+        for (feature, interval, sens_specific) in requests {
+            self.set_feature(feature, interval, sens_specific)?;
         }
+        Ok(())
     }
 
-    pub fn update_sensors(&mut self) -> bool {
-        self.delay.delay_ms(2);
-        if let Ok(mut out) = self.wait_for_packet(3, Some(SH2Read::GetFeatureResponse), Some(10)) {
-            if out.data_length() > 5 {
-                self.parse_sensor_report(out);
-                true
-            } else {
-                false
+    fn set_feature(
+        &mut self,
+        feature_id: ReportId,
+        interval: Option<u32>,
+        sens_specific: Option<u32>,
+    ) -> Result<(), SensorError<T::Error>> {
+        let data_buffer = state::set_feature_command(feature_id, interval, sens_specific);
+        warn!("ENABLE FEATURES OUTPUT: {:?}", &data_buffer);
+        let timeout_ms = self.state.timeouts.response_ms;
+        let control = self.state.channels.control;
+        self.send_packet_from_data(control, &data_buffer)?;
+
+        match self.wait_for_packet(control, Some(SH2Read::GetFeatureResponse), timeout_ms) {
+            Ok(_) => {
+                self.state
+                    .remember_feature(feature_id, interval, sens_specific);
+                warn!("FEATURE ENABLED");
+                Ok(())
             }
-        } else {
-            false
-        }
-    }
-
-    fn parse_sensor_report(&mut self, mut out: Packet) {
-        let mut data = out.as_mut_data(false);
-        let timestamping: &[u8] = &data[0..5];
-        let mut index = 5;
-        let max = data.len().checked_sub(15).unwrap_or(2);
-        let mut attempts = 0;
-        while index < data.len() && attempts < max {
-            if let Some((id, length)) = get_report_length(data[index]) {
-                self.sensors.update_data(
-                    id,
-                    &data[(index + 4)..(index + length as usize)],
-                    &data[(index)..(index + 4)],
-                );
-                index += length as usize;
+            Err(e) => {
+                warn!("FAILED TO ENABLE FEATURE {:?}", feature_id);
+                Err(e)
             }
-            attempts += 1;
         }
     }
 
-    fn increment_seq_num(&mut self, read_write: bool, channel: u8, seq_num: Option<u8>) -> u8 {
-        if channel < 6 {
-            match read_write {
-                true => {
-                    let temp = self.seq_num_w[channel as usize] as u16;
-                    self.seq_num_w[channel as usize] = ((temp + 1) % 256) as u8;
-                    ((temp + 1) % 256) as u8
-                }
-                false => {
-                    if let Some(num) = seq_num {
-                        self.seq_num_r[channel as usize] = num;
-                        num
-                    } else {
-                        let temp = self.seq_num_r[channel as usize] as u16;
-                        self.seq_num_r[channel as usize] = ((temp + 1) % 256) as u8;
-                        ((temp + 1) % 256) as u8
-                    }
-                }
-            }
-        } else {
-            0
+    /// Reads the next input report into the sensor values. `false` means
+    /// none arrived and the values are unchanged.
+    ///
+    /// Features lost to an unsolicited reset are enabled again before the
+    /// read. A restore that fails is tried again on the next call.
+    pub fn update_sensors(&mut self) -> Result<bool, SensorError<T::Error>> {
+        // The hub forgets its features when it resets on its own. A reset
+        // during the restore asks for another one.
+        if self.state.take_restore()
+            && let Err(e) = self.restore_features()
+        {
+            self.state.restore_failed();
+            return Err(e);
         }
+
+        let input = self.state.channels.input_normal;
+        let read = self.wait_for_packet(input, None, INPUT_TIMEOUT_MS);
+        self.state.input_received(read)
     }
 
-    pub fn frs_read(&mut self, record_id: FRSConfiguration) {
+    /// Reads FRS record `record_id` into `words`. Returns how many words
+    /// the record holds, those past the end of `words` are dropped.
+    pub fn frs_read(
+        &mut self,
+        record_id: FRSConfiguration,
+        words: &mut [u32],
+    ) -> Result<usize, SensorError<T::Error>> {
         let mut frs_data = FRSDataRead::new(record_id);
-        let mut write = Packet::from_data_buf(
-            &frs_data.generate_read_request().ok().unwrap(),
-            2,
-            self.seq_num_w[1],
-            false,
-        )
-        .expect("Failed to generate Packet for FRS READ");
-
-        self.send_packet_from_data(2, write.as_mut_data(false));
-        println!("LOOKING FOR PACKET");
-        let packet = self.wait_for_packet(2, Some(SH2Read::FrsReadResponse), Some(10));
-        println!("FETCH COMPLETE");
-        if let Ok(mut packet) = packet {
-            frs_data
-                .process_read_response(packet.as_mut_data(false))
-                .expect("failed to parse FRS Response");
-            println!("FRS RESPONSE : {:?}", frs_data);
+        let request = frs_data.generate_read_request();
+
+        let timeout_ms = self.state.timeouts.response_ms;
+        let control = self.state.channels.control;
+        self.send_packet_from_data(control, &request)?;
+        // Records come two words per response
+        loop {
+            let packet = self.wait_for_packet_where(
+                control,
+                Some(SH2Read::FrsReadResponse),
+                timeout_ms,
+                |packet| frs_data.answers(packet.cargo()),
+            )?;
+            if frs_data.process_read_response(packet.cargo(), words)? {
+                info!("FRS RESPONSE : {:?}", frs_data);
+                return Ok(frs_data.word_count());
+            }
         }
     }
 }
 
-impl<I2C, D> BNO08x<I2C, D>
+// The getters hand back the raw report tuples
+#[allow(clippy::type_complexity)]
+impl<T, D, const N: usize> BNO08x<T, D, N>
 where
-    I2C: I2c,
+    T: Transport,
     D: DelayNs,
 {
-    pub fn accelerometer(&mut self) -> (Status, f32, f32, f32) {
-        self.update_sensors();
-        self.sensors.acceleration
+    pub fn accelerometer(&mut self) -> Result<(Status, f32, f32, f32), SensorError<T::Error>> {
+        self.update_sensors()?;
+        Ok(self.state.sensors.acceleration)
     }
 
-    pub fn raw_accelerometer(&mut self) -> (Status, u16, u16, u16, u32) {
-        self.update_sensors();
-        self.sensors.accel_raw
+    pub fn raw_accelerometer(
+        &mut self,
+    ) -> Result<(Status, u16, u16, u16, u32), SensorError<T::Error>> {
+        self.update_sensors()?;
+        Ok(self.state.sensors.accel_raw)
     }
 
-    pub fn gyroscope(&mut self) -> (Status, f32, f32, f32) {
-        self.update_sensors();
-        self.sensors.gyroscope
+    pub fn gyroscope(&mut self) -> Result<(Status, f32, f32, f32), SensorError<T::Error>> {
+        self.update_sensors()?;
+        Ok(self.state.sensors.gyroscope)
     }
 
-    pub fn raw_gyroscope(&mut self) -> (Status, u16, u16, u16, u16, u32) {
-        self.update_sensors();
-        self.sensors.gyro_raw
+    pub fn raw_gyroscope(
+        &mut self,
+    ) -> Result<(Status, u16, u16, u16, u16, u32), SensorError<T::Error>> {
+        self.update_sensors()?;
+        Ok(self.state.sensors.gyro_raw)
     }
 
-    pub fn magnetometer(&mut self) -> (Status, f32, f32, f32) {
-        self.update_sensors();
-        self.sensors.magnetometer
+    pub fn magnetometer(&mut self) -> Result<(Status, f32, f32, f32), SensorError<T::Error>> {
+        self.update_sensors()?;
+        Ok(self.state.sensors.magnetometer)
     }
 
-    pub fn raw_magnetomter(&mut self) -> (Status, u16, u16, u16, u32) {
-        self.update_sensors();
-        self.sensors.mag_raw
+    pub fn raw_magnetomter(
+        &mut self,
+    ) -> Result<(Status, u16, u16, u16, u32), SensorError<T::Error>> {
+        self.update_sensors()?;
+        Ok(self.state.sensors.mag_raw)
     }
 
-    pub fn linear_acceleration(&mut self) -> (Status, f32, f32, f32) {
-        self.update_sensors();
-        self.sensors.linear_accel
+    pub fn linear_acceleration(
+        &mut self,
+    ) -> Result<(Status, f32, f32, f32), SensorError<T::Error>> {
+        self.update_sensors()?;
+        Ok(self.state.sensors.linear_accel)
     }
 
-    pub fn gravity(&mut self) -> (Status, f32, f32, f32) {
-        self.update_sensors();
-        self.sensors.gravity
+    pub fn gravity(&mut self) -> Result<(Status, f32, f32, f32), SensorError<T::Error>> {
+        self.update_sensors()?;
+        Ok(self.state.sensors.gravity)
     }
 
-    pub fn quaternions(&mut self) -> (Status, f32, f32, f32, f32) {
+    pub fn quaternions(&mut self) -> Result<(Status, f32, f32, f32, f32), SensorError<T::Error>> {
         // info!("READING QUATERNIONS");
-        self.update_sensors();
-        self.sensors.quaternions
+        self.update_sensors()?;
+        Ok(self.state.sensors.quaternions)
     }
 }
 
+/// Driver errors. `E` is the error type of the transport in use.
 #[derive(Copy, Clone, Debug)]
-pub enum SensorError {
+pub enum SensorError<E> {
     Placeholder, // Add other errors for your driver here.
     Unimplemented,
     PacketRetrievalFailed,
     InvalidLength,
+    Packet(PacketError),
+    /// The bus itself failed, e.g. a NACK or lost arbitration
+    Bus(E),
+    /// Nothing answered within the configured [`Timeouts`]. `report_id` is
+    /// `None` when any report on `channel` would have done.
+    Timeout {
+        channel: u8,
+        report_id: Option<u8>,
+    },
+    /// The hub answered `command` with a non-zero status
+    CommandFailed {
+        command: Command,
+        status: u8,
+    },
+    /// The hub couldn't serve an FRS read
+    Frs(FRSStatus),
 }
@@ -1,7 +1,6 @@
-use defmt::Format;
 use heapless::Vec;
 
-use crate::{data, register::*};
+use crate::register::*;
 
 const REPORT_LENGTHS: &[(ReportId, u8)] = &[
     (ReportId::AccelerometerRaw, 16),
@@ -156,7 +155,9 @@ const FEATURE_DEPENDENCIES: &[(ReportId, &[ReportId])] = &[
     ),
 ];
 
-#[derive(Format)]
+#[allow(dead_code)]
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum DataVals {
     I16(i16),
     I32(i32),
@@ -176,7 +177,7 @@ impl DataVals {
                 DataVals::I32(i32::from_le_bytes(bytes.try_into().unwrap_or([0_u8; 4])))
             }
             DataTypes::Reserved => DataVals::Reserved,
-            DataTypes::U8 => DataVals::U8(if bytes.len() > 0 { bytes[1] } else { 0 }),
+            DataTypes::U8 => DataVals::U8(bytes.first().copied().unwrap_or(0)),
             DataTypes::U16 => {
                 DataVals::U16(u16::from_le_bytes(bytes.try_into().unwrap_or([0_u8; 2])))
             }
@@ -187,6 +188,7 @@ impl DataVals {
     }
 }
 
+#[allow(dead_code)]
 #[derive(Copy, Clone)]
 pub enum DataTypes {
     I16,
@@ -390,6 +392,7 @@ const REPORT_VALS: &[(ReportId, &[DataTypes])] = &[
     (ReportId::ShakeDetector, &[DataTypes::U16]),
 ];
 
+#[allow(dead_code)]
 const CONTROL_REPORT_LENGTHS: &[(SH2Read, u8)] = &[
     (SH2Read::CommandResponse, 16),
     (SH2Read::FrsReadResponse, 16),
@@ -428,7 +431,7 @@ pub fn process_buf(data_format: &[DataTypes], buf: &[u8]) -> Vec<DataVals, 20> {
             DataTypes::I16 => {
                 output
                     .push(DataVals::get_value(
-                        &buf[(buf_index)..(buf_index + 2)],
+                        buf.get(buf_index..buf_index + 2).unwrap_or(&[]),
                         *format,
                     ))
                     .ok();
@@ -437,7 +440,7 @@ pub fn process_buf(data_format: &[DataTypes], buf: &[u8]) -> Vec<DataVals, 20> {
             DataTypes::I32 => {
                 output
                     .push(DataVals::get_value(
-                        &buf[(buf_index)..(buf_index + 4)],
+                        buf.get(buf_index..buf_index + 4).unwrap_or(&[]),
                         *format,
                     ))
                     .ok();
@@ -446,7 +449,7 @@ pub fn process_buf(data_format: &[DataTypes], buf: &[u8]) -> Vec<DataVals, 20> {
             DataTypes::U8 => {
                 output
                     .push(DataVals::get_value(
-                        &buf[(buf_index)..(buf_index + 1)],
+                        buf.get(buf_index..buf_index + 1).unwrap_or(&[]),
                         *format,
                     ))
                     .ok();
@@ -455,7 +458,7 @@ pub fn process_buf(data_format: &[DataTypes], buf: &[u8]) -> Vec<DataVals, 20> {
             DataTypes::U16 => {
                 output
                     .push(DataVals::get_value(
-                        &buf[(buf_index)..(buf_index + 2)],
+                        buf.get(buf_index..buf_index + 2).unwrap_or(&[]),
                         *format,
                     ))
                     .ok();
@@ -464,7 +467,7 @@ pub fn process_buf(data_format: &[DataTypes], buf: &[u8]) -> Vec<DataVals, 20> {
             DataTypes::U32 => {
                 output
                     .push(DataVals::get_value(
-                        &buf[(buf_index)..(buf_index + 4)],
+                        buf.get(buf_index..buf_index + 4).unwrap_or(&[]),
                         *format,
                     ))
                     .ok();
@@ -473,7 +476,7 @@ pub fn process_buf(data_format: &[DataTypes], buf: &[u8]) -> Vec<DataVals, 20> {
             DataTypes::Reserved => {}
         }
     }
-    return output;
+    output
 }
 
 pub fn get_feature_dependencies(report_id: ReportId) -> &'static [ReportId] {
@@ -485,6 +488,7 @@ pub fn get_feature_dependencies(report_id: ReportId) -> &'static [ReportId] {
     &[]
 }
 
+#[allow(dead_code)]
 pub fn get_control_report_length(report_id: u8) -> Option<(SH2Read, u8)> {
     for (r_id, data) in CONTROL_REPORT_LENGTHS {
         if *r_id as u8 == report_id {
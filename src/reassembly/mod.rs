@@ -0,0 +1,135 @@
+// Refer to SH2-Reference-Manual 1.3.1 (SHTP)
+//
+// A cargo longer than one transfer is split by the hub. The first fragment
+// carries the full length, every following one has bit 15 of the length
+// set and counts only what is left. Fragments of one cargo use consecutive
+// sequence numbers on their channel but may be interleaved with traffic on
+// other channels.
+
+use heapless::Vec;
+
+use crate::data::{Packet, PacketError};
+
+const CHANNELS: usize = 6;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum SlotState {
+    Empty,
+    Partial,
+    Complete,
+}
+
+struct Slot<const N: usize> {
+    state: SlotState,
+    expected: usize,
+    seq_num: u8,
+    cargo: Vec<u8, N>,
+}
+
+impl<const N: usize> Slot<N> {
+    fn new() -> Self {
+        Slot {
+            state: SlotState::Empty,
+            expected: 0,
+            seq_num: 0,
+            cargo: Vec::new(),
+        }
+    }
+
+    fn clear(&mut self) {
+        self.state = SlotState::Empty;
+        self.cargo.clear();
+    }
+
+    fn store(&mut self, packet: &mut Packet<N>, state: SlotState) -> Result<(), PacketError> {
+        self.clear();
+        self.cargo
+            .extend_from_slice(packet.as_mut_data(false))
+            .map_err(|_| PacketError::TooLarge)?;
+        self.expected = packet.data_length() as usize;
+        self.seq_num = packet.seq_num();
+        self.state = state;
+        Ok(())
+    }
+
+    fn take(&mut self, channel: u8) -> Option<Packet<N>> {
+        let out = Packet::from_data_buf(&self.cargo, channel, self.seq_num, false).ok();
+        self.clear();
+        out
+    }
+}
+
+/// Joins continuation fragments back into one packet per channel. A cargo
+/// is held up to `N` bytes, longer ones are dropped as `TooLarge`.
+pub struct Reassembler<const N: usize> {
+    slots: [Slot<N>; CHANNELS],
+}
+
+impl<const N: usize> Reassembler<N> {
+    pub fn new() -> Self {
+        Reassembler {
+            slots: core::array::from_fn(|_| Slot::new()),
+        }
+    }
+
+    /// Returns a packet that was completed while reporting an earlier error.
+    pub fn take_complete(&mut self) -> Option<Packet<N>> {
+        let channel = self
+            .slots
+            .iter()
+            .position(|slot| slot.state == SlotState::Complete)?;
+        self.slots[channel].take(channel as u8)
+    }
+
+    /// Feeds one transfer. Returns the logical packet once every fragment
+    /// has arrived, or `None` while more are expected.
+    pub fn push(&mut self, mut packet: Packet<N>) -> Result<Option<Packet<N>>, PacketError> {
+        let channel = packet.channel();
+        let Some(slot) = self.slots.get_mut(channel as usize) else {
+            return Ok(Some(packet));
+        };
+
+        if !packet.is_continuation() {
+            let stale = slot.state == SlotState::Partial;
+            if packet.is_complete() && !stale {
+                return Ok(Some(packet));
+            }
+
+            let state = if packet.is_complete() {
+                SlotState::Complete
+            } else {
+                SlotState::Partial
+            };
+            slot.store(&mut packet, state)?;
+
+            // The tail of the previous cargo never arrived
+            return if stale {
+                Err(PacketError::MissingFragment)
+            } else {
+                Ok(None)
+            };
+        }
+
+        if slot.state != SlotState::Partial || packet.seq_num() != slot.seq_num.wrapping_add(1) {
+            slot.clear();
+            return Err(PacketError::MissingFragment);
+        }
+
+        slot.seq_num = packet.seq_num();
+        if slot
+            .cargo
+            .extend_from_slice(packet.as_mut_data(false))
+            .is_err()
+        {
+            slot.clear();
+            return Err(PacketError::TooLarge);
+        }
+
+        if slot.cargo.len() >= slot.expected {
+            slot.cargo.truncate(slot.expected);
+            Ok(slot.take(channel))
+        } else {
+            Ok(None)
+        }
+    }
+}
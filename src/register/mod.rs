@@ -1,9 +1,8 @@
 // Refer to SH2-Reference-Manual
 
-use defmt::Format;
-
 #[allow(dead_code)]
-#[derive(Copy, Clone, PartialEq, Eq, Format)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(u8)]
 pub enum ReportId {
     AccelerometerRaw = 0x14,        // Report Length 16
@@ -61,7 +60,8 @@ pub enum Status {
     Unknown,
 }
 
-impl Format for Status {
+#[cfg(feature = "defmt")]
+impl defmt::Format for Status {
     fn format(&self, fmt: defmt::Formatter) {
         match self {
             Status::Unreliable => defmt::write!(fmt, "Unreliable"),
@@ -107,6 +107,26 @@ pub enum SH2Write {
     CommandRequest = 0xF2,
 }
 
+// Refer to SH2-Reference-Manual 1.3.1, executable channel
+
+#[allow(dead_code)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum ExecCommand {
+    Reset = 0x01,
+    On = 0x02,
+    Sleep = 0x03,
+}
+
+#[allow(dead_code)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum ExecResponse {
+    ResetComplete = 0x01,
+}
+
 #[repr(u8)]
 pub enum I2CAddress {
     Default = 0x4A,
@@ -115,7 +135,8 @@ pub enum I2CAddress {
 
 #[allow(dead_code)]
 #[repr(u16)]
-#[derive(Clone, Copy, PartialEq, Eq, Debug, defmt::Format)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum FRSConfiguration {
     AgmStaticCalibration = 0x7979,
     AgmNominalCalibration = 0x4D4D,
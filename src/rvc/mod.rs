@@ -0,0 +1,132 @@
+// Refer to BNO08x datasheet 1.4.4 (UART-RVC)
+//
+// In RVC mode the hub streams fixed frames at 100 Hz with no SHTP
+// handshake. Each frame is laid out as:
+//
+// | 0..2   | 2     | 3..9           | 9..15           | 15 | 16 | 17       | 18       |
+// | 0xAAAA | index | yaw pitch roll | accel x y z     | MI | MR | reserved | checksum |
+//
+// Angles are in 0.01 degree steps and accelerations in mg. The checksum is
+// the low byte of the sum of bytes 2 through 17.
+
+use embedded_io::Read;
+
+use crate::config::{RVC_ANGLE_SCALE, STANDARD_GRAVITY};
+
+pub const RVC_FRAME_LENGTH: usize = 19;
+const HEADER: u8 = 0xAA;
+
+/// One decoded RVC frame. RVC frames carry no accuracy bits, so unlike the
+/// SHTP reports there is no [`Status`](crate::register::Status) attached.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RvcFrame {
+    pub index: u8,
+    /// Degrees
+    pub yaw: f32,
+    /// Degrees
+    pub pitch: f32,
+    /// Degrees
+    pub roll: f32,
+    /// m/s^2
+    pub acceleration: (f32, f32, f32),
+    pub motion_intent: u8,
+    pub motion_request: u8,
+}
+
+impl RvcFrame {
+    fn from_buf(buf: &[u8; RVC_FRAME_LENGTH]) -> Self {
+        let value = |offset: usize| i16::from_le_bytes([buf[offset], buf[offset + 1]]);
+        let angle = |offset: usize| value(offset) as f32 * RVC_ANGLE_SCALE;
+        let accel = |offset: usize| value(offset) as f32 * STANDARD_GRAVITY / 1000.0;
+
+        RvcFrame {
+            index: buf[2],
+            yaw: angle(3),
+            pitch: angle(5),
+            roll: angle(7),
+            acceleration: (accel(9), accel(11), accel(13)),
+            motion_intent: buf[15],
+            motion_request: buf[16],
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RvcError {
+    Checksum,
+}
+
+/// Byte fed RVC frame parser. Bytes before a 0xAAAA header are dropped and
+/// a frame failing its checksum is rescanned for the next header, so the
+/// parser can be started mid stream.
+pub struct RvcParser {
+    buf: [u8; RVC_FRAME_LENGTH],
+    len: usize,
+}
+
+impl Default for RvcParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RvcParser {
+    pub fn new() -> Self {
+        RvcParser {
+            buf: [0; RVC_FRAME_LENGTH],
+            len: 0,
+        }
+    }
+
+    /// Feeds one byte. Returns a result once a full frame has been seen.
+    pub fn push(&mut self, byte: u8) -> Option<Result<RvcFrame, RvcError>> {
+        if self.len < 2 && byte != HEADER {
+            self.len = 0;
+            return None;
+        }
+        self.buf[self.len] = byte;
+        self.len += 1;
+
+        if self.len < RVC_FRAME_LENGTH {
+            return None;
+        }
+
+        let checksum = self.buf[2..18]
+            .iter()
+            .fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+        if checksum == self.buf[18] {
+            self.len = 0;
+            Some(Ok(RvcFrame::from_buf(&self.buf)))
+        } else {
+            self.resync();
+            Some(Err(RvcError::Checksum))
+        }
+    }
+
+    /// Reads from `uart` until a valid frame is decoded. Frames with a bad
+    /// checksum are skipped. Returns `None` once the stream runs dry.
+    pub fn read_frame<U: Read>(&mut self, uart: &mut U) -> Result<Option<RvcFrame>, U::Error> {
+        let mut byte = [0u8; 1];
+        loop {
+            if uart.read(&mut byte)? == 0 {
+                return Ok(None);
+            }
+            if let Some(Ok(frame)) = self.push(byte[0]) {
+                return Ok(Some(frame));
+            }
+        }
+    }
+
+    fn resync(&mut self) {
+        let start = (1..self.len)
+            .find(|&index| {
+                self.buf[index] == HEADER
+                    && (index + 1 == self.len || self.buf[index + 1] == HEADER)
+            })
+            .unwrap_or(self.len);
+        self.buf.copy_within(start..self.len, 0);
+        self.len -= start;
+    }
+}
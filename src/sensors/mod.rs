@@ -1,5 +1,3 @@
-use defmt::*;
-
 use crate::{
     config::*,
     parsing::{get_report_format, process_buf, q_point_processing},
@@ -46,14 +44,10 @@ impl Sensors {
                     let out = process_buf(data_format, data_slice);
                     let mut accel_vals = [0.0_f32; 3];
                     for (index, data_val) in out.iter().enumerate() {
-                        match data_val {
-                            crate::parsing::DataVals::I16(num) => {
-                                if index < 3 {
-                                    accel_vals[index] =
-                                        q_point_processing(*num, ACCEL_SCALAR_Q_POINT)
-                                }
-                            }
-                            _ => {}
+                        if let crate::parsing::DataVals::I16(num) = data_val
+                            && index < 3
+                        {
+                            accel_vals[index] = q_point_processing(*num, ACCEL_SCALAR_Q_POINT)
                         }
                     }
                     self.acceleration.0 = status;
@@ -66,15 +60,11 @@ impl Sensors {
                     let mut accel_vals = [0_u32; 4];
                     for (index, data_val) in out.iter().enumerate() {
                         match data_val {
-                            crate::parsing::DataVals::U16(num) => {
-                                if index < 3 {
-                                    accel_vals[index] = *num as u32;
-                                }
+                            crate::parsing::DataVals::U16(num) if index < 3 => {
+                                accel_vals[index] = *num as u32;
                             }
-                            crate::parsing::DataVals::U32(timestamp) => {
-                                if index == 3 {
-                                    accel_vals[3] = *timestamp;
-                                }
+                            crate::parsing::DataVals::U32(timestamp) if index == 3 => {
+                                accel_vals[3] = *timestamp;
                             }
                             _ => {}
                         }
@@ -89,13 +79,10 @@ impl Sensors {
                     let out = process_buf(data_format, data_slice);
                     let mut linear = [0.0_f32; 3];
                     for (index, data_val) in out.iter().enumerate() {
-                        match data_val {
-                            crate::parsing::DataVals::I16(num) => {
-                                if index < 3 {
-                                    linear[index] = q_point_processing(*num, ACCEL_SCALAR_Q_POINT)
-                                }
-                            }
-                            _ => {}
+                        if let crate::parsing::DataVals::I16(num) = data_val
+                            && index < 3
+                        {
+                            linear[index] = q_point_processing(*num, ACCEL_SCALAR_Q_POINT)
                         }
                     }
                     self.linear_accel.0 = status;
@@ -107,13 +94,10 @@ impl Sensors {
                     let out = process_buf(data_format, data_slice);
                     let mut grav = [0.0_f32; 3];
                     for (index, data_val) in out.iter().enumerate() {
-                        match data_val {
-                            crate::parsing::DataVals::I16(num) => {
-                                if index < 3 {
-                                    grav[index] = q_point_processing(*num, ACCEL_SCALAR_Q_POINT)
-                                }
-                            }
-                            _ => {}
+                        if let crate::parsing::DataVals::I16(num) = data_val
+                            && index < 3
+                        {
+                            grav[index] = q_point_processing(*num, ACCEL_SCALAR_Q_POINT)
                         }
                     }
                     self.gravity.0 = status;
@@ -125,13 +109,10 @@ impl Sensors {
                     let out = process_buf(data_format, data_slice);
                     let mut gyro_vals = [0.0_f32; 3];
                     for (index, data_val) in out.iter().enumerate() {
-                        match data_val {
-                            crate::parsing::DataVals::I16(num) => {
-                                if index < 3 {
-                                    gyro_vals[index] = q_point_processing(*num, GYRO_SCALAR_Q_POINT)
-                                }
-                            }
-                            _ => {}
+                        if let crate::parsing::DataVals::I16(num) = data_val
+                            && index < 3
+                        {
+                            gyro_vals[index] = q_point_processing(*num, GYRO_SCALAR_Q_POINT)
                         }
                     }
                     self.gyroscope.0 = status;
@@ -144,15 +125,11 @@ impl Sensors {
                     let mut gyro_vals = [0_u32; 5];
                     for (index, data_val) in out.iter().enumerate() {
                         match data_val {
-                            crate::parsing::DataVals::U16(num) => {
-                                if index < 4 {
-                                    gyro_vals[index] = *num as u32;
-                                }
+                            crate::parsing::DataVals::U16(num) if index < 4 => {
+                                gyro_vals[index] = *num as u32;
                             }
-                            crate::parsing::DataVals::U32(timestamp) => {
-                                if index == 4 {
-                                    gyro_vals[index] = *timestamp;
-                                }
+                            crate::parsing::DataVals::U32(timestamp) if index == 4 => {
+                                gyro_vals[index] = *timestamp;
                             }
                             _ => {}
                         }
@@ -168,13 +145,10 @@ impl Sensors {
                     let out = process_buf(data_format, data_slice);
                     let mut mag_vals = [0.0_f32; 3];
                     for (index, data_val) in out.iter().enumerate() {
-                        match data_val {
-                            crate::parsing::DataVals::I16(num) => {
-                                if index < 3 {
-                                    mag_vals[index] = q_point_processing(*num, MAG_SCALAR_Q_POINT)
-                                }
-                            }
-                            _ => {}
+                        if let crate::parsing::DataVals::I16(num) = data_val
+                            && index < 3
+                        {
+                            mag_vals[index] = q_point_processing(*num, MAG_SCALAR_Q_POINT)
                         }
                     }
                     self.magnetometer.0 = status;
@@ -187,15 +161,11 @@ impl Sensors {
                     let mut mag_vals = [0_u32; 4];
                     for (index, data_val) in out.iter().enumerate() {
                         match data_val {
-                            crate::parsing::DataVals::U16(num) => {
-                                if index < 3 {
-                                    mag_vals[index] = *num as u32;
-                                }
+                            crate::parsing::DataVals::U16(num) if index < 3 => {
+                                mag_vals[index] = *num as u32;
                             }
-                            crate::parsing::DataVals::U32(timestamp) => {
-                                if index == 3 {
-                                    mag_vals[3] = *timestamp;
-                                }
+                            crate::parsing::DataVals::U32(timestamp) if index == 3 => {
+                                mag_vals[3] = *timestamp;
                             }
                             _ => {}
                         }
@@ -210,18 +180,15 @@ impl Sensors {
                     let out = process_buf(data_format, data_slice);
                     let mut quat_vals = [0.0_f32; 4];
                     for (index, data_val) in out.iter().enumerate() {
-                        match data_val {
-                            crate::parsing::DataVals::I16(num) => {
-                                if index < 4 {
-                                    quat_vals[index] = q_point_processing(*num, QUAT_SCALAR_Q_POINT)
-                                } else if index == 4 {
-                                    debug!(
-                                        "Quaternion processing accuracy: {}",
-                                        q_point_processing(*num, 12)
-                                    );
-                                }
+                        if let crate::parsing::DataVals::I16(num) = data_val {
+                            if index < 4 {
+                                quat_vals[index] = q_point_processing(*num, QUAT_SCALAR_Q_POINT)
+                            } else if index == 4 {
+                                debug!(
+                                    "Quaternion processing accuracy: {}",
+                                    q_point_processing(*num, 12)
+                                );
                             }
-                            _ => {}
                         }
                     }
                     self.quaternions.0 = status;
@@ -234,13 +201,10 @@ impl Sensors {
                     let out = process_buf(data_format, data_slice);
                     let mut quat_vals = [0.0_f32; 4];
                     for (index, data_val) in out.iter().enumerate() {
-                        match data_val {
-                            crate::parsing::DataVals::I16(num) => {
-                                if index < 4 {
-                                    quat_vals[index] = q_point_processing(*num, QUAT_SCALAR_Q_POINT)
-                                }
-                            }
-                            _ => {}
+                        if let crate::parsing::DataVals::I16(num) = data_val
+                            && index < 4
+                        {
+                            quat_vals[index] = q_point_processing(*num, QUAT_SCALAR_Q_POINT)
                         }
                     }
                     self.game_quaternions.0 = status;
@@ -253,18 +217,15 @@ impl Sensors {
                     let out = process_buf(data_format, data_slice);
                     let mut quat_vals = [0.0_f32; 4];
                     for (index, data_val) in out.iter().enumerate() {
-                        match data_val {
-                            crate::parsing::DataVals::I16(num) => {
-                                if index < 4 {
-                                    quat_vals[index] = q_point_processing(*num, QUAT_SCALAR_Q_POINT)
-                                } else if index == 4 {
-                                    debug!(
-                                        "Quaternion processing accuracy: {}",
-                                        q_point_processing(*num, 12)
-                                    );
-                                }
+                        if let crate::parsing::DataVals::I16(num) = data_val {
+                            if index < 4 {
+                                quat_vals[index] = q_point_processing(*num, QUAT_SCALAR_Q_POINT)
+                            } else if index == 4 {
+                                debug!(
+                                    "Quaternion processing accuracy: {}",
+                                    q_point_processing(*num, 12)
+                                );
                             }
-                            _ => {}
                         }
                     }
                     self.geomag_quaternions.0 = status;
@@ -286,27 +247,16 @@ pub fn parse_status(report_bytes: &[u8]) -> (Status, u16) {
     if report_bytes.len() >= 4 {
         let status_byte = report_bytes[2];
         // info!("STATUS BYTE: {:#X}", status_byte);
-        let status;
         let delay_lower_byte = report_bytes[3];
         let delay_upper = status_byte >> 2;
         let delay = (delay_upper as u16) << 8 | delay_lower_byte as u16;
-        match status_byte & 0b0000_0011 {
-            0 => {
-                status = Status::Unreliable;
-            }
-            1 => {
-                status = Status::LowAccuracy;
-            }
-            2 => {
-                status = Status::MediumAccuracy;
-            }
-            3 => {
-                status = Status::HighAccuracy;
-            }
-            _ => {
-                status = Status::Unknown;
-            }
-        }
+        let status = match status_byte & 0b0000_0011 {
+            0 => Status::Unreliable,
+            1 => Status::LowAccuracy,
+            2 => Status::MediumAccuracy,
+            3 => Status::HighAccuracy,
+            _ => Status::Unknown,
+        };
 
         (status, delay)
     } else {
@@ -0,0 +1,570 @@
+// A stand-in hub for host tests. It speaks SHTP over I2C the way a BNO08x
+// does: the advertisement and reset complete after power on, replies on the
+// control channel and a stream of scripted input reports on channel 3.
+//
+// Reads follow the I2C rules of the real part. A read of just the header
+// doesn't consume anything, and a read shorter than the transfer leaves
+// the rest to be sent as a continuation.
+
+use heapless::{Deque, Vec};
+
+use crate::advertisement::ADVERTISEMENT_REPORT_ID;
+use crate::command::{CalibrationConfig, Command};
+use crate::register::*;
+
+const COMMAND: u8 = 0;
+const EXECUTABLE: u8 = 1;
+const CONTROL: u8 = 2;
+const INPUT_NORMAL: u8 = 3;
+
+const FRAME_CAPACITY: usize = 320;
+const REPORT_CAPACITY: usize = 32;
+
+type Cargo = Vec<u8, FRAME_CAPACITY>;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SimError {
+    /// Addressed to another device on the bus
+    Nack,
+    /// Frame written by the host doesn't fit or has a bad header
+    Frame,
+}
+
+impl embedded_hal::i2c::Error for SimError {
+    fn kind(&self) -> embedded_hal::i2c::ErrorKind {
+        match self {
+            SimError::Nack => embedded_hal::i2c::ErrorKind::NoAcknowledge(
+                embedded_hal::i2c::NoAcknowledgeSource::Address,
+            ),
+            SimError::Frame => embedded_hal::i2c::ErrorKind::Other,
+        }
+    }
+}
+
+struct Transfer {
+    channel: u8,
+    cargo: Cargo,
+    sent: usize,
+}
+
+/// Simulated BNO08x on an I2C bus. Implements both the blocking and the
+/// async `I2c` traits so it can stand in for the bus of either driver.
+pub struct SimulatedBno08x {
+    address: u8,
+    seq_num: [u8; 6],
+    outbox: Deque<Transfer, 16>,
+    pending: Option<Transfer>,
+    reports: Deque<Vec<u8, REPORT_CAPACITY>, 64>,
+    features: Vec<(u8, u32), 42>,
+    frs: Vec<(u16, Vec<u32, 16>), 8>,
+    product_id: [u8; 14],
+    asleep: bool,
+    resets: u32,
+    commands: Vec<[u8; 12], 16>,
+    errors: Vec<[u8; 6], 8>,
+    /// Reports offered and sent per sensor
+    counts: Vec<(u8, u32, u32), 16>,
+    dcd_saves: u32,
+    dcd_autosave: bool,
+    /// Status DCD saves answer with
+    dcd_status: u8,
+    calibration: CalibrationConfig,
+}
+
+impl SimulatedBno08x {
+    /// Powers up a hub answering on `address`, with its startup messages
+    /// already queued.
+    pub fn new(address: u8) -> Self {
+        let mut sim = SimulatedBno08x {
+            address,
+            seq_num: [0; 6],
+            outbox: Deque::new(),
+            pending: None,
+            reports: Deque::new(),
+            features: Vec::new(),
+            frs: Vec::new(),
+            product_id: [0; 14],
+            asleep: false,
+            resets: 0,
+            commands: Vec::new(),
+            errors: Vec::new(),
+            counts: Vec::new(),
+            dcd_saves: 0,
+            dcd_autosave: true,
+            dcd_status: 0,
+            calibration: CalibrationConfig {
+                accel: true,
+                gyro: true,
+                mag: true,
+                planar: false,
+                on_table: false,
+            },
+        };
+        sim.set_product_id((3, 2), 10003608, 5, 0);
+        sim.reset();
+        sim
+    }
+
+    /// Values reported in the ProductIDResponse.
+    pub fn set_product_id(&mut self, sw_ver: (u8, u8), part_num: u32, build_num: u32, patch: u16) {
+        self.product_id[0] = 1; // Power on reset
+        self.product_id[1] = sw_ver.0;
+        self.product_id[2] = sw_ver.1;
+        self.product_id[3..7].copy_from_slice(&part_num.to_le_bytes());
+        self.product_id[7..11].copy_from_slice(&build_num.to_le_bytes());
+        self.product_id[11..13].copy_from_slice(&patch.to_le_bytes());
+    }
+
+    /// Queues an input report, report ID first, for channel 3. It is only
+    /// sent once its feature is enabled, and dropped if it isn't by the
+    /// time it comes up.
+    pub fn push_report(&mut self, report: &[u8]) -> bool {
+        Vec::from_slice(report)
+            .ok()
+            .and_then(|report| self.reports.push_back(report).ok())
+            .is_some()
+    }
+
+    /// Queues `cargo` on the control channel as if the hub had sent it
+    /// unprompted.
+    pub fn push_control(&mut self, cargo: &[u8]) -> bool {
+        let queued = self.outbox.len();
+        self.send(CONTROL, cargo);
+        self.outbox.len() > queued
+    }
+
+    /// Stores an FRS record to be served to FRS read requests.
+    pub fn set_frs_record(&mut self, record: FRSConfiguration, words: &[u32]) -> bool {
+        let record = record as u16;
+        self.frs.retain(|(id, _)| *id != record);
+        match Vec::from_slice(words) {
+            Ok(words) => self.frs.push((record, words)).is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    /// Report interval of an enabled feature, in microseconds.
+    pub fn feature_interval(&self, feature: ReportId) -> Option<u32> {
+        self.features
+            .iter()
+            .find(|(id, _)| *id == feature as u8)
+            .map(|(_, interval)| *interval)
+    }
+
+    pub fn is_asleep(&self) -> bool {
+        self.asleep
+    }
+
+    /// Resets seen since power on, the power on itself included.
+    pub fn resets(&self) -> u32 {
+        self.resets
+    }
+
+    /// Times the DCD was saved on request.
+    pub fn dcd_saves(&self) -> u32 {
+        self.dcd_saves
+    }
+
+    pub fn dcd_autosave(&self) -> bool {
+        self.dcd_autosave
+    }
+
+    /// Makes the DCD save command answer with `status`, non-zero being a
+    /// failure.
+    pub fn set_dcd_status(&mut self, status: u8) {
+        self.dcd_status = status;
+    }
+
+    /// Background calibrations currently running.
+    pub fn calibration_config(&self) -> CalibrationConfig {
+        self.calibration
+    }
+
+    /// Queues an error for the error report command. Fields are laid out
+    /// like the response: severity, sequence, source, error, module, code.
+    pub fn push_error(&mut self, error: [u8; 6]) -> bool {
+        self.errors.push(error).is_ok()
+    }
+
+    /// Command requests received so far, oldest first. Only the first 16
+    /// are kept.
+    pub fn commands(&self) -> &[[u8; 12]] {
+        &self.commands
+    }
+
+    /// Restarts the hub as if NRST was pulsed. Features are disabled and
+    /// the startup messages are sent again.
+    pub fn reset(&mut self) {
+        self.seq_num = [0; 6];
+        self.outbox.clear();
+        self.pending = None;
+        self.features.clear();
+        self.asleep = false;
+        self.resets += 1;
+
+        self.send(COMMAND, &advertisement());
+        self.send(EXECUTABLE, &[ExecResponse::ResetComplete as u8]);
+        let initialize = Command::Initialize as u8 | 0x80;
+        self.command_response(initialize, 0, 0, &[0; 11]);
+    }
+
+    fn send(&mut self, channel: u8, cargo: &[u8]) {
+        if let Ok(cargo) = Vec::from_slice(cargo) {
+            self.outbox
+                .push_back(Transfer {
+                    channel,
+                    cargo,
+                    sent: 0,
+                })
+                .ok();
+        }
+    }
+
+    fn next_transfer(&mut self) -> Option<Transfer> {
+        if let Some(transfer) = self.outbox.pop_front() {
+            return Some(transfer);
+        }
+        if self.asleep {
+            return None;
+        }
+
+        while let Some(report) = self.reports.pop_front() {
+            let Some(&id) = report.first() else {
+                continue;
+            };
+            let enabled = self.features.iter().any(|(f, _)| *f == id);
+            self.count(id, enabled);
+            if enabled {
+                // Base timestamp reference, then the report
+                let mut cargo: Cargo = Vec::from_slice(&[0xFB, 0, 0, 0, 0]).ok()?;
+                cargo.extend_from_slice(&report).ok()?;
+                return Some(Transfer {
+                    channel: INPUT_NORMAL,
+                    cargo,
+                    sent: 0,
+                });
+            }
+        }
+        None
+    }
+
+    fn count(&mut self, sensor: u8, sent: bool) {
+        if !self.counts.iter().any(|(id, _, _)| *id == sensor) {
+            self.counts.push((sensor, 0, 0)).ok();
+        }
+        if let Some((_, offered, attained)) =
+            self.counts.iter_mut().find(|(id, _, _)| *id == sensor)
+        {
+            *offered += 1;
+            *attained += sent as u32;
+        }
+    }
+
+    fn read_transfer(&mut self, buf: &mut [u8]) {
+        buf.fill(0);
+        if self.pending.is_none() {
+            self.pending = self.next_transfer();
+        }
+        let Some(transfer) = self.pending.as_mut() else {
+            return;
+        };
+
+        let remaining = transfer.cargo.len() - transfer.sent;
+        let mut length = remaining as u16 + 4;
+        if transfer.sent > 0 {
+            length |= 0x8000;
+        }
+        let seq_num = &mut self.seq_num[transfer.channel as usize];
+        let length = length.to_le_bytes();
+        let header = [length[0], length[1], transfer.channel, *seq_num];
+        let count = buf.len().min(4);
+        buf[..count].copy_from_slice(&header[..count]);
+        // Reading only the header leaves the transfer queued
+        if buf.len() <= 4 {
+            return;
+        }
+
+        let count = (buf.len() - 4).min(remaining);
+        buf[4..4 + count].copy_from_slice(&transfer.cargo[transfer.sent..transfer.sent + count]);
+        transfer.sent += count;
+        *seq_num = seq_num.wrapping_add(1);
+        if transfer.sent == transfer.cargo.len() {
+            self.pending = None;
+        }
+    }
+
+    fn receive(&mut self, frame: &[u8]) -> Result<(), SimError> {
+        if frame.len() < 4 {
+            return Err(SimError::Frame);
+        }
+        let length = (u16::from_le_bytes([frame[0], frame[1]]) & 0x7FFF) as usize;
+        let cargo = frame.get(4..length).ok_or(SimError::Frame)?;
+        let Some(&report_id) = cargo.first() else {
+            return Ok(());
+        };
+
+        match frame[2] {
+            EXECUTABLE => match report_id {
+                1 => self.reset(),
+                2 => self.asleep = false,
+                3 => self.asleep = true,
+                _ => {}
+            },
+            CONTROL => self.control(report_id, cargo),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn control(&mut self, report_id: u8, cargo: &[u8]) {
+        if report_id == Register::Write(SH2Write::ProductIDRequest).addr() {
+            let mut response = [0u8; 16];
+            response[0] = Register::Read(SH2Read::ProductIDResponse).addr();
+            response[1..15].copy_from_slice(&self.product_id);
+            self.send(CONTROL, &response);
+        } else if report_id == Register::Write(SH2Write::SetFeatureCommand).addr()
+            && cargo.len() >= 17
+        {
+            let feature = cargo[1];
+            let interval = u32::from_le_bytes([cargo[5], cargo[6], cargo[7], cargo[8]]);
+            self.features.retain(|(id, _)| *id != feature);
+            if interval > 0 {
+                self.features.push((feature, interval)).ok();
+            }
+            let mut response = [0u8; 17];
+            response[0] = Register::Read(SH2Read::GetFeatureResponse).addr();
+            response[1..17].copy_from_slice(&cargo[1..17]);
+            self.send(CONTROL, &response);
+        } else if report_id == Register::Write(SH2Write::GetFeatureRequest).addr()
+            && cargo.len() >= 2
+        {
+            let interval = self
+                .features
+                .iter()
+                .find(|(id, _)| *id == cargo[1])
+                .map_or(0, |(_, interval)| *interval);
+            let mut response = [0u8; 17];
+            response[0] = Register::Read(SH2Read::GetFeatureResponse).addr();
+            response[1] = cargo[1];
+            response[5..9].copy_from_slice(&interval.to_le_bytes());
+            self.send(CONTROL, &response);
+        } else if report_id == Register::Write(SH2Write::FrsReadRequest).addr() && cargo.len() >= 8
+        {
+            let offset = u16::from_le_bytes([cargo[2], cargo[3]]);
+            let record = u16::from_le_bytes([cargo[4], cargo[5]]);
+            self.frs_read(record, offset);
+        } else if report_id == Register::Write(SH2Write::CommandRequest).addr()
+            && let Some(request) = cargo.first_chunk::<12>()
+        {
+            self.commands.push(*request).ok();
+            self.command(request);
+        }
+    }
+
+    fn command(&mut self, request: &[u8; 12]) {
+        let (seq, command, params) = (request[1], request[2], &request[3..]);
+        let mut fields = [0u8; 11];
+        match Command::try_from(command) {
+            Ok(Command::ErrorReport) => {
+                let severity = params[0];
+                let mut response_seq = 0;
+                for error in self.errors.clone().iter().filter(|e| e[0] <= severity) {
+                    fields[..6].copy_from_slice(error);
+                    self.command_response(command, seq, response_seq, &fields);
+                    response_seq += 1;
+                }
+                // Source 0xFF closes the list
+                fields = [0; 11];
+                fields[2] = 0xFF;
+                self.command_response(command, seq, response_seq, &fields);
+            }
+            Ok(Command::Counter) => {
+                let sensor = params[1];
+                let (offered, attained) = self
+                    .counts
+                    .iter()
+                    .find(|(id, _, _)| *id == sensor)
+                    .map_or((0, 0), |(_, offered, attained)| (*offered, *attained));
+                if params[0] == 0x01 {
+                    self.counts.retain(|(id, _, _)| *id != sensor);
+                    return;
+                }
+                // Offered and accepted, then produced and attained. Every
+                // sample taken while on is reported here.
+                fields[..4].copy_from_slice(&offered.to_le_bytes());
+                fields[4..8].copy_from_slice(&attained.to_le_bytes());
+                self.command_response(command, seq, 0, &fields);
+                fields[..4].copy_from_slice(&attained.to_le_bytes());
+                self.command_response(command, seq, 1, &fields);
+            }
+            Ok(Command::Initialize) => {
+                // Status success, then the subsystem
+                fields[1] = params[0];
+                self.command_response(command, seq, 0, &fields);
+            }
+            Ok(Command::SaveDcd) => {
+                if self.dcd_status == 0 {
+                    self.dcd_saves += 1;
+                }
+                fields[0] = self.dcd_status;
+                self.command_response(command, seq, 0, &fields);
+            }
+            // Applied without a response, see SH2-Reference-Manual 6.4.7
+            Ok(Command::DcdPeriodicSave) => self.dcd_autosave = params[0] == 0,
+            Ok(Command::ClearDcdAndReset) => self.reset(),
+            Ok(Command::MeCalibration) => {
+                // P3 zero configures, anything else just asks
+                if params[3] == 0 {
+                    self.calibration = CalibrationConfig {
+                        accel: params[0] != 0,
+                        gyro: params[1] != 0,
+                        mag: params[2] != 0,
+                        planar: params[4] != 0,
+                        on_table: params[5] != 0,
+                    };
+                }
+                let c = self.calibration;
+                fields[1..6].copy_from_slice(&[
+                    c.accel as u8,
+                    c.gyro as u8,
+                    c.mag as u8,
+                    c.planar as u8,
+                    c.on_table as u8,
+                ]);
+                self.command_response(command, seq, 0, &fields);
+            }
+            _ => {}
+        }
+    }
+
+    fn command_response(&mut self, command: u8, seq: u8, response_seq: u8, fields: &[u8; 11]) {
+        let mut response = [0u8; 16];
+        response[0] = Register::Read(SH2Read::CommandResponse).addr();
+        response[2] = command;
+        response[3] = seq;
+        response[4] = response_seq;
+        response[5..].copy_from_slice(fields);
+        self.send(CONTROL, &response);
+    }
+
+    fn frs_read(&mut self, record: u16, offset: u16) {
+        let words: Vec<u32, 16> = self
+            .frs
+            .iter()
+            .find(|(id, _)| *id == record)
+            .map(|(_, words)| words.clone())
+            .unwrap_or_default();
+
+        let mut response = [0u8; 16];
+        response[0] = Register::Read(SH2Read::FrsReadResponse).addr();
+        response[12..14].copy_from_slice(&record.to_le_bytes());
+        let mut index = offset as usize;
+        if index >= words.len() {
+            response[1] = 5; // Record empty
+            self.send(CONTROL, &response);
+            return;
+        }
+
+        while index < words.len() {
+            let chunk = &words[index..words.len().min(index + 2)];
+            let last = index + chunk.len() == words.len();
+            // Data length in the upper nibble, read complete on the last
+            response[1] = ((chunk.len() as u8) << 4) | if last { 3 } else { 0 };
+            response[2..4].copy_from_slice(&(index as u16).to_le_bytes());
+            response[4..12].fill(0);
+            for (slot, word) in response[4..12].chunks_mut(4).zip(chunk) {
+                slot.copy_from_slice(&word.to_le_bytes());
+            }
+            self.send(CONTROL, &response);
+            index += chunk.len();
+        }
+    }
+
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [embedded_hal::i2c::Operation<'_>],
+    ) -> Result<(), SimError> {
+        if address != self.address {
+            return Err(SimError::Nack);
+        }
+
+        // Adjacent writes are one transfer on the wire
+        let mut written: Vec<u8, FRAME_CAPACITY> = Vec::new();
+        for operation in operations {
+            match operation {
+                embedded_hal::i2c::Operation::Read(buf) => self.read_transfer(buf),
+                embedded_hal::i2c::Operation::Write(buf) => written
+                    .extend_from_slice(buf)
+                    .map_err(|_| SimError::Frame)?,
+            }
+        }
+        if written.is_empty() {
+            Ok(())
+        } else {
+            self.receive(&written)
+        }
+    }
+}
+
+impl embedded_hal::i2c::ErrorType for SimulatedBno08x {
+    type Error = SimError;
+}
+
+impl embedded_hal::i2c::I2c for SimulatedBno08x {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [embedded_hal::i2c::Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        SimulatedBno08x::transaction(self, address, operations)
+    }
+}
+
+impl embedded_hal_async::i2c::I2c for SimulatedBno08x {
+    async fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [embedded_hal::i2c::Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        SimulatedBno08x::transaction(self, address, operations)
+    }
+}
+
+fn tlv(out: &mut Cargo, tag: u8, value: &[u8]) {
+    out.push(tag).ok();
+    out.push(value.len() as u8).ok();
+    out.extend_from_slice(value).ok();
+}
+
+/// The advertisement a BNO08x sends after reset.
+fn advertisement() -> Cargo {
+    let mut cargo = Cargo::new();
+    cargo.push(ADVERTISEMENT_REPORT_ID).ok();
+    tlv(&mut cargo, 1, &0u32.to_le_bytes());
+    tlv(&mut cargo, 2, &256u16.to_le_bytes());
+    tlv(&mut cargo, 3, &512u16.to_le_bytes());
+    tlv(&mut cargo, 4, &256u16.to_le_bytes());
+    tlv(&mut cargo, 5, &256u16.to_le_bytes());
+    tlv(&mut cargo, 8, b"SHTP\0");
+    tlv(&mut cargo, 0x80, b"1.0.1\0");
+    tlv(&mut cargo, 6, &[COMMAND]);
+    tlv(&mut cargo, 9, b"command\0");
+    tlv(&mut cargo, 1, &1u32.to_le_bytes());
+    tlv(&mut cargo, 8, b"executable\0");
+    tlv(&mut cargo, 6, &[EXECUTABLE]);
+    tlv(&mut cargo, 9, b"device\0");
+    tlv(&mut cargo, 1, &2u32.to_le_bytes());
+    tlv(&mut cargo, 8, b"sensorhub\0");
+    tlv(&mut cargo, 0x80, b"1.0.0\0");
+    tlv(&mut cargo, 6, &[CONTROL]);
+    tlv(&mut cargo, 9, b"control\0");
+    tlv(&mut cargo, 6, &[INPUT_NORMAL]);
+    tlv(&mut cargo, 9, b"inputNormal\0");
+    tlv(&mut cargo, 7, &[4]);
+    tlv(&mut cargo, 9, b"inputWake\0");
+    tlv(&mut cargo, 6, &[5]);
+    tlv(&mut cargo, 9, b"inputGyroRv\0");
+    cargo
+}
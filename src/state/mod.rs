@@ -0,0 +1,482 @@
+// Protocol bookkeeping shared by the blocking and async drivers. Nothing in
+// here touches the bus, the drivers feed it packets and send what it builds.
+
+use heapless::Vec;
+
+use crate::SensorError;
+use crate::advertisement::{ADVERTISEMENT_REPORT_ID, Advertisement, ChannelMap};
+use crate::command::{
+    COMMAND_REQUEST_LENGTH, Command, CommandRequest, CommandResponse, ERROR_REPORT_CAPACITY,
+    ErrorRecord,
+};
+use crate::config::{DEFAULT_REPORT_INTERVAL, Timeouts};
+use crate::data::{Packet, PacketError, ProductId, SequenceStats};
+use crate::parsing::{get_feature_dependencies, get_report_length};
+use crate::reassembly::Reassembler;
+use crate::register::*;
+use crate::sensors::Sensors;
+
+/// A feature with its report interval and sensor specific setting.
+pub type FeatureConfig = (ReportId, Option<u32>, Option<u32>);
+
+pub struct DriverState<const N: usize> {
+    pub seq_num_w: [u8; 6],
+    pub seq_num_r: [SequenceStats; 6],
+    pub sensors: Sensors,
+    pub features: Vec<ReportId, 42>,
+    /// Every feature enabled so far with its settings, kept across resets so
+    /// they can be sent again.
+    pub feature_config: Vec<FeatureConfig, 42>,
+    pub reassembler: Reassembler<N>,
+    pub advertisement: Option<Advertisement>,
+    pub channels: ChannelMap,
+    /// Read limit from the last advertisement, waiting to be handed to the
+    /// transport.
+    pub max_read: Option<u16>,
+    pub timeouts: Timeouts,
+    /// Sequence number for the next command request
+    pub command_seq: u8,
+    /// A reset complete is on its way, power on or one the driver asked for.
+    /// Dropped once the hub sends anything else, or when the wait for it
+    /// times out.
+    pub expect_reset: bool,
+    /// Reset complete seen, the matching unsolicited Initialize response
+    /// hasn't arrived yet
+    pub awaiting_init: bool,
+    /// The hub reset on its own and the features haven't been sent again
+    pub restore_pending: bool,
+    /// The hub reset on its own since the application last asked
+    pub reset_event: bool,
+    /// A packet clocked in during a write, handed out by the next read
+    pub pending: Option<Packet<N>>,
+}
+
+impl<const N: usize> DriverState<N> {
+    pub fn new() -> Self {
+        DriverState {
+            seq_num_w: [0; 6],
+            seq_num_r: [SequenceStats::default(); 6],
+            sensors: Sensors::new(),
+            features: Vec::new(),
+            feature_config: Vec::new(),
+            reassembler: Reassembler::new(),
+            advertisement: None,
+            channels: ChannelMap::default(),
+            max_read: None,
+            timeouts: Timeouts::default(),
+            command_seq: 0,
+            expect_reset: true,
+            awaiting_init: false,
+            restore_pending: false,
+            reset_event: false,
+            pending: None,
+        }
+    }
+
+    pub fn increment_seq_num(&mut self, channel: u8) -> u8 {
+        if let Some(seq_num) = self.seq_num_w.get_mut(channel as usize) {
+            *seq_num = seq_num.wrapping_add(1);
+            *seq_num
+        } else {
+            0
+        }
+    }
+
+    pub fn next_command_seq(&mut self) -> u8 {
+        let seq = self.command_seq;
+        self.command_seq = seq.wrapping_add(1);
+        seq
+    }
+
+    /// Numbers `request` and lays it out for the control channel. Returns
+    /// the command sequence number with it.
+    pub fn command_request(
+        &mut self,
+        request: CommandRequest,
+    ) -> (u8, [u8; COMMAND_REQUEST_LENGTH]) {
+        let seq = self.next_command_seq();
+        (seq, request.encode(seq))
+    }
+
+    /// Header for `cargo_length` bytes going out on `channel`, numbered
+    /// with the channel's next sequence number.
+    pub fn write_header(
+        &mut self,
+        channel: u8,
+        cargo_length: usize,
+    ) -> Result<[u8; 4], PacketError> {
+        if channel as usize >= self.seq_num_w.len() {
+            return Err(PacketError::InvalidChannel);
+        }
+        // Bit 15 of the length is the continuation flag
+        let length = u16::try_from(cargo_length + 4)
+            .ok()
+            .filter(|length| length & 0x8000 == 0)
+            .ok_or(PacketError::TooLarge)?;
+        let seq = self.increment_seq_num(channel);
+        let [low, high] = length.to_le_bytes();
+        Ok([low, high, channel, seq])
+    }
+
+    /// Feeds one transfer from the transport. Returns the logical packet
+    /// once all of its fragments have arrived.
+    pub fn receive(&mut self, packet: Packet<N>) -> Result<Option<Packet<N>>, PacketError> {
+        // info!("R PACK LENGTH: {}", out.packet_length());
+        // info!("R SEQ NUM: {}", out.seq_num());
+        if packet.packet_length() > 0
+            && let Some(stats) = self.seq_num_r.get_mut(packet.channel() as usize)
+        {
+            stats.record(packet.seq_num());
+        }
+
+        match self.reassembler.push(packet) {
+            Ok(Some(mut out)) => {
+                self.process_advertisement(&mut out);
+                self.process_reset(&out);
+                Ok(Some(out))
+            }
+            Ok(None) => Ok(None),
+            Err(e) => {
+                warn!("Dropped fragmented packet: {:?}", e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Takes what the hub sent while a frame was being written. Only a
+    /// full duplex bus delivers anything here.
+    pub fn receive_during_write(&mut self, packet: Packet<N>) {
+        if packet.packet_length() == 0 {
+            return;
+        }
+        // Fragment errors were logged, a packet completed meanwhile is
+        // still picked up by take_complete
+        if let Ok(Some(out)) = self.receive(packet)
+            && self.pending.replace(out).is_some()
+        {
+            warn!("Dropped a packet received during a write");
+        }
+    }
+
+    /// A packet received during a write, or one completed while an
+    /// earlier fragment error was reported.
+    pub fn take_complete(&mut self) -> Option<Packet<N>> {
+        if let Some(out) = self.pending.take() {
+            return Some(out);
+        }
+        let mut out = self.reassembler.take_complete()?;
+        self.process_advertisement(&mut out);
+        self.process_reset(&out);
+        Some(out)
+    }
+
+    fn process_advertisement(&mut self, packet: &mut Packet<N>) {
+        if packet.channel() != self.channels.command
+            || packet.data_length() == 0
+            || packet.report_id() != ADVERTISEMENT_REPORT_ID
+        {
+            return;
+        }
+
+        if let Some(advertisement) = Advertisement::parse(packet.as_mut_data(false)) {
+            self.channels = ChannelMap::from_advertisement(&advertisement);
+            // Reads land in the packet buffer header first, which leaves
+            // N - 4 bytes for the cargo
+            if advertisement.max_cargo_read as usize > N.saturating_sub(4) {
+                warn!(
+                    "Hub sends up to {} bytes of cargo, larger ones will be dropped",
+                    advertisement.max_cargo_read
+                );
+            }
+            if advertisement.max_transfer_read > 0 {
+                self.max_read = Some(advertisement.max_transfer_read);
+            }
+            debug!("Advertisement received, channels {:?}", self.channels);
+            self.advertisement = Some(advertisement);
+        }
+    }
+
+    /// Picks out resets the driver didn't ask for. The hub announces a
+    /// reset with a reset complete and an unsolicited Initialize response,
+    /// either one is enough to notice it.
+    fn process_reset(&mut self, packet: &Packet<N>) {
+        if self.is_reset_complete(packet) {
+            self.awaiting_init = true;
+            if self.expect_reset {
+                self.expect_reset = false;
+            } else {
+                self.on_unsolicited_reset();
+            }
+        } else if packet.channel() == self.channels.control
+            && let Some(response) = CommandResponse::parse(packet.cargo())
+            && response.unsolicited
+            && response.command == Command::Initialize as u8
+        {
+            if self.awaiting_init {
+                self.awaiting_init = false;
+            } else {
+                self.on_unsolicited_reset();
+            }
+        } else if packet.packet_length() > 0 && packet.channel() != self.channels.command {
+            // Only the advertisement comes ahead of a reset complete,
+            // anything else means the hub is up and none is on its way
+            self.expect_reset = false;
+        }
+    }
+
+    fn on_unsolicited_reset(&mut self) {
+        warn!("Hub reset unexpectedly");
+        self.features.clear();
+        for stats in self.seq_num_r.iter_mut() {
+            stats.last = None;
+        }
+        self.restore_pending = true;
+        self.reset_event = true;
+    }
+
+    /// The hub drops every report and numbers from zero again after a
+    /// reset, which must not count as a gap.
+    pub fn on_reset(&mut self) {
+        self.features.clear();
+        self.advertisement = None;
+        for stats in self.seq_num_r.iter_mut() {
+            stats.last = None;
+        }
+        self.expect_reset = true;
+    }
+
+    /// A soft reset starts over: the remembered features are forgotten as
+    /// well, so no later reset turns them back on.
+    pub fn on_soft_reset(&mut self) {
+        self.on_reset();
+        self.feature_config.clear();
+        self.restore_pending = false;
+    }
+
+    /// Records a feature the hub confirmed, replacing earlier settings.
+    pub fn remember_feature(
+        &mut self,
+        feature_id: ReportId,
+        interval: Option<u32>,
+        sens_specific: Option<u32>,
+    ) {
+        self.features.push(feature_id).ok();
+        let config = (feature_id, interval, sens_specific);
+        match self.feature_config.iter_mut().find(|c| c.0 == feature_id) {
+            Some(existing) => *existing = config,
+            None => {
+                self.feature_config.push(config).ok();
+            }
+        }
+    }
+
+    pub fn is_reset_complete(&self, packet: &Packet<N>) -> bool {
+        packet.channel() == self.channels.executable
+            && packet.data_length() > 0
+            && packet.report_id() == ExecResponse::ResetComplete as u8
+    }
+
+    /// Set feature commands that get `feature_id` running, dependencies
+    /// first with default settings. Empty when it is on already.
+    pub fn feature_requests<E>(
+        &self,
+        feature_id: ReportId,
+        interval: Option<u32>,
+        sens_specific: Option<u32>,
+    ) -> Result<Vec<FeatureConfig, 8>, SensorError<E>> {
+        if self.features.contains(&feature_id) {
+            return Ok(Vec::new());
+        }
+        if feature_id == ReportId::PersonalActClassifier {
+            debug!("Unimplemented");
+            return Err(SensorError::Unimplemented);
+        }
+
+        let mut order = Vec::new();
+        self.push_with_dependencies(feature_id, &mut order);
+        Ok(order
+            .into_iter()
+            .map(|feature| {
+                if feature == feature_id {
+                    (feature, interval, sens_specific)
+                } else {
+                    (feature, None, None)
+                }
+            })
+            .collect())
+    }
+
+    /// Whether the features lost to an unsolicited reset should be sent
+    /// again now. Hand the request back with
+    /// [`restore_failed`](Self::restore_failed) if that doesn't work out.
+    pub fn take_restore(&mut self) -> bool {
+        core::mem::take(&mut self.restore_pending)
+    }
+
+    pub fn restore_failed(&mut self) {
+        self.restore_pending = true;
+    }
+
+    /// Takes the outcome of waiting for an input report. Returns whether the
+    /// sensor values changed, running out of time isn't an error.
+    pub fn input_received<E>(
+        &mut self,
+        read: Result<Packet<N>, SensorError<E>>,
+    ) -> Result<bool, SensorError<E>> {
+        match read {
+            Ok(out) if out.data_length() > 5 => {
+                self.parse_sensor_report(out);
+                Ok(true)
+            }
+            Ok(_) | Err(SensorError::Timeout { .. }) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Gives up on a reset complete, a late one counts as unsolicited.
+    pub fn reset_timeout<E>(&mut self) -> SensorError<E> {
+        warn!("No reset complete within {} ms", self.timeouts.reset_ms);
+        self.expect_reset = false;
+        SensorError::Timeout {
+            channel: self.channels.executable,
+            report_id: Some(ExecResponse::ResetComplete as u8),
+        }
+    }
+
+    fn push_with_dependencies(&self, feature_id: ReportId, order: &mut Vec<ReportId, 8>) {
+        for dep in get_feature_dependencies(feature_id) {
+            if !self.features.contains(dep) && !order.contains(dep) {
+                self.push_with_dependencies(*dep, order);
+            }
+        }
+        if !order.contains(&feature_id) {
+            order.push(feature_id).ok();
+        }
+    }
+
+    pub fn parse_sensor_report(&mut self, mut out: Packet<N>) {
+        let data = out.as_mut_data(false);
+        // First 5 bytes hold the base timestamp reference
+        let mut index = 5;
+        let max = data.len().checked_sub(15).unwrap_or(2);
+        let mut attempts = 0;
+        while index < data.len() && attempts < max {
+            if let Some((id, length)) = get_report_length(data[index]) {
+                let end = index + length as usize;
+                if end > data.len() {
+                    warn!("Dropped report {:?} cut short by the transfer", id);
+                    break;
+                }
+                self.sensors
+                    .update_data(id, &data[(index + 4)..end], &data[index..(index + 4)]);
+                index = end;
+            }
+            attempts += 1;
+        }
+    }
+}
+
+/// What a wait loop does after one read.
+pub enum WaitStep<const N: usize, E> {
+    /// The wait is over, with the packet or the error that ended it
+    Done(Result<Packet<N>, SensorError<E>>),
+    /// Something else arrived, read again straight away
+    Skip,
+    /// Nothing arrived, give the hub a moment
+    Idle,
+}
+
+/// Sorts the outcome of one read in a wait for `channel`. Bus errors end
+/// the wait, others count like an empty read.
+pub fn wait_step<const N: usize, E>(
+    read: Result<Packet<N>, SensorError<E>>,
+    channel: u8,
+    report_id: Option<SH2Read>,
+    accept: impl Fn(&Packet<N>) -> bool,
+) -> WaitStep<N, E> {
+    match read {
+        Ok(out) if matches(&out, channel, report_id) && accept(&out) => WaitStep::Done(Ok(out)),
+        Ok(out) if out.packet_length() > 0 => WaitStep::Skip,
+        Err(SensorError::Bus(e)) => WaitStep::Done(Err(SensorError::Bus(e))),
+        _ => WaitStep::Idle,
+    }
+}
+
+pub fn timed_out<E>(channel: u8, report_id: Option<SH2Read>) -> SensorError<E> {
+    warn!("Timed out waiting on channel {}", channel);
+    SensorError::Timeout {
+        channel,
+        report_id: report_id.map(|id| Register::Read(id).addr()),
+    }
+}
+
+/// Whether `packet` is the response to request `seq` of `command`.
+pub fn answers<const N: usize>(packet: &Packet<N>, command: Command, seq: u8) -> bool {
+    CommandResponse::parse(packet.cargo()).is_some_and(|response| response.answers(command, seq))
+}
+
+pub fn command_response<const N: usize, E>(
+    packet: &Packet<N>,
+) -> Result<CommandResponse, SensorError<E>> {
+    CommandResponse::parse(packet.cargo()).ok_or(SensorError::InvalidLength)
+}
+
+/// Fails with the status in R0 of `response` unless it is zero.
+pub fn check_status<E>(
+    command: Command,
+    response: CommandResponse,
+) -> Result<CommandResponse, SensorError<E>> {
+    match response.status() {
+        0 => Ok(response),
+        status => Err(SensorError::CommandFailed { command, status }),
+    }
+}
+
+/// Adds the error carried by `response` to `errors`. `false` once the
+/// hub marks the end of the list.
+pub fn collect_error(
+    errors: &mut Vec<ErrorRecord, ERROR_REPORT_CAPACITY>,
+    response: &CommandResponse,
+) -> bool {
+    let Some(error) = ErrorRecord::from_response(response) else {
+        return false;
+    };
+    if errors.push(error).is_err() {
+        warn!("Error report full, dropping {:?}", error);
+    }
+    true
+}
+
+pub fn product_id_request() -> [u8; 2] {
+    [Register::Write(SH2Write::ProductIDRequest).addr(), 0x00]
+}
+
+/// Whether a product ID response carries a software version.
+pub fn has_product_id<const N: usize>(mut packet: Packet<N>) -> bool {
+    ProductId::new(packet.as_mut_data(false)).display().0 != (0, 0)
+}
+
+pub fn matches<const N: usize>(
+    packet: &Packet<N>,
+    channel: u8,
+    report_id: Option<SH2Read>,
+) -> bool {
+    packet.channel() == channel
+        && packet.data_length() > 0
+        && report_id.is_none_or(|id| packet.report_id() == Register::Read(id).addr())
+}
+
+pub fn set_feature_command(
+    feature_id: ReportId,
+    interval: Option<u32>,
+    sens_specific: Option<u32>,
+) -> [u8; 17] {
+    let mut data_buffer = [0_u8; 17];
+    data_buffer[0] = Register::Write(SH2Write::SetFeatureCommand).addr();
+    data_buffer[1] = feature_id as u8;
+    data_buffer[5..9].copy_from_slice(&u32::to_le_bytes(
+        interval.unwrap_or(DEFAULT_REPORT_INTERVAL),
+    ));
+    data_buffer[13..17].copy_from_slice(&u32::to_le_bytes(sens_specific.unwrap_or(0)));
+    data_buffer
+}
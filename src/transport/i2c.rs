@@ -0,0 +1,280 @@
+use core::convert::Infallible;
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{ErrorType, InputPin, OutputPin};
+use embedded_hal::i2c::{I2c, Operation};
+use embedded_hal_async::delay::DelayNs as AsyncDelayNs;
+use embedded_hal_async::digital::Wait;
+use embedded_hal_async::i2c::I2c as AsyncI2c;
+
+use crate::config::I2C_INTERRUPT_POLL_NS;
+use crate::data::Packet;
+use crate::error::I2cError;
+use crate::transport::{AsyncTransport, Transport, wait_for_interrupt, wait_for_interrupt_async};
+
+/// SHTP over I2C. The hub repeats the header at the start of every read, so
+/// packets are returned with the four byte spacer in front of the cargo.
+///
+/// With an INT pin the bus is only read once the hub signals data-ready.
+/// Without one, every read polls the header and waits 5 ms before fetching
+/// the cargo.
+///
+/// An NRST pin added with [`with_reset`](I2cTransport::with_reset) enables
+/// hard resets.
+pub struct I2cTransport<I2C, IP = NoInterrupt, RP = NoReset> {
+    i2c: I2C,
+    interrupt: Option<IP>,
+    reset: Option<RP>,
+    address: u8,
+    max_read: u16,
+}
+
+/// Placeholder for an [`I2cTransport`] without an INT pin.
+pub struct NoInterrupt;
+
+impl ErrorType for NoInterrupt {
+    type Error = Infallible;
+}
+
+impl InputPin for NoInterrupt {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(false)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(true)
+    }
+}
+
+impl Wait for NoInterrupt {
+    async fn wait_for_high(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Placeholder for an [`I2cTransport`] without an NRST pin.
+pub struct NoReset;
+
+impl ErrorType for NoReset {
+    type Error = Infallible;
+}
+
+impl OutputPin for NoReset {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl<I2C> I2cTransport<I2C> {
+    pub fn new(i2c: I2C, address: u8) -> Self {
+        I2cTransport {
+            i2c,
+            interrupt: None,
+            reset: None,
+            address,
+            max_read: u16::MAX,
+        }
+    }
+}
+
+impl<I2C, IP> I2cTransport<I2C, IP> {
+    /// `interrupt` is the hub's active low INT (H_INTN) line.
+    pub fn with_interrupt(i2c: I2C, address: u8, interrupt: IP) -> Self {
+        I2cTransport {
+            i2c,
+            interrupt: Some(interrupt),
+            reset: None,
+            address,
+            max_read: u16::MAX,
+        }
+    }
+}
+
+impl<I2C, IP, RP> I2cTransport<I2C, IP, RP> {
+    /// Hands the transport the hub's active low NRST line.
+    pub fn with_reset<RP2>(self, reset: RP2) -> I2cTransport<I2C, IP, RP2> {
+        I2cTransport {
+            i2c: self.i2c,
+            interrupt: self.interrupt,
+            reset: Some(reset),
+            address: self.address,
+            max_read: self.max_read,
+        }
+    }
+
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+
+    pub fn release_with_interrupt(self) -> (I2C, Option<IP>) {
+        (self.i2c, self.interrupt)
+    }
+
+    pub fn release_with_pins(self) -> (I2C, Option<IP>, Option<RP>) {
+        (self.i2c, self.interrupt, self.reset)
+    }
+}
+
+impl<I2C, IP, RP> Transport for I2cTransport<I2C, IP, RP>
+where
+    I2C: I2c,
+    IP: InputPin,
+    RP: OutputPin,
+{
+    type Error = I2cError<I2C::Error>;
+
+    fn read_packet<const N: usize, D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        timeout_ns: u32,
+    ) -> Result<Packet<N>, Self::Error> {
+        if let Some(interrupt) = self.interrupt.as_mut()
+            && !wait_for_interrupt(interrupt, delay, I2C_INTERRUPT_POLL_NS, timeout_ns)
+                .map_err(|_| I2cError::Pin)?
+        {
+            return Ok(Packet::new(true));
+        }
+
+        let mut header = [0u8; 4];
+        self.i2c
+            .read(self.address, &mut header)
+            .map_err(I2cError::I2c)?;
+
+        let mut out = Packet::from_header(&header, true);
+        out.limit_transfer(self.max_read as usize);
+        if self.interrupt.is_none() {
+            delay.delay_ms(5);
+        }
+        self.i2c
+            .read(self.address, out.as_mut_data(true))
+            .map_err(I2cError::I2c)?;
+
+        Ok(out)
+    }
+
+    fn set_max_read(&mut self, max_read: u16) {
+        self.max_read = max_read;
+    }
+
+    fn write_packet<const N: usize, D: DelayNs>(
+        &mut self,
+        header: &[u8; 4],
+        cargo: &[u8],
+        _delay: &mut D,
+    ) -> Result<Packet<N>, Self::Error> {
+        // Adjacent writes go out back to back, so the cargo needn't be
+        // copied behind the header
+        self.i2c
+            .transaction(
+                self.address,
+                &mut [Operation::Write(header), Operation::Write(cargo)],
+            )
+            .map_err(I2cError::I2c)?;
+        Ok(Packet::new(true))
+    }
+
+    fn hard_reset<D: DelayNs>(&mut self, delay: &mut D) -> Result<bool, Self::Error> {
+        let Some(reset) = self.reset.as_mut() else {
+            return Ok(false);
+        };
+        reset.set_high().map_err(|_| I2cError::Pin)?;
+        delay.delay_ms(10);
+        reset.set_low().map_err(|_| I2cError::Pin)?;
+        delay.delay_ms(10);
+        reset.set_high().map_err(|_| I2cError::Pin)?;
+        Ok(true)
+    }
+}
+
+impl<I2C, IP, RP> AsyncTransport for I2cTransport<I2C, IP, RP>
+where
+    I2C: AsyncI2c,
+    IP: Wait,
+    RP: OutputPin,
+{
+    type Error = I2cError<I2C::Error>;
+
+    async fn read_packet<const N: usize, D: AsyncDelayNs>(
+        &mut self,
+        delay: &mut D,
+        timeout_ns: u32,
+    ) -> Result<Packet<N>, Self::Error> {
+        if let Some(interrupt) = self.interrupt.as_mut()
+            && !wait_for_interrupt_async(interrupt, delay, timeout_ns)
+                .await
+                .map_err(|_| I2cError::Pin)?
+        {
+            return Ok(Packet::new(true));
+        }
+
+        let mut header = [0u8; 4];
+        self.i2c
+            .read(self.address, &mut header)
+            .await
+            .map_err(I2cError::I2c)?;
+
+        let mut out = Packet::from_header(&header, true);
+        out.limit_transfer(self.max_read as usize);
+        if self.interrupt.is_none() {
+            delay.delay_ms(5).await;
+        }
+        self.i2c
+            .read(self.address, out.as_mut_data(true))
+            .await
+            .map_err(I2cError::I2c)?;
+
+        Ok(out)
+    }
+
+    fn set_max_read(&mut self, max_read: u16) {
+        self.max_read = max_read;
+    }
+
+    async fn write_packet<const N: usize, D: AsyncDelayNs>(
+        &mut self,
+        header: &[u8; 4],
+        cargo: &[u8],
+        _delay: &mut D,
+    ) -> Result<Packet<N>, Self::Error> {
+        self.i2c
+            .transaction(
+                self.address,
+                &mut [Operation::Write(header), Operation::Write(cargo)],
+            )
+            .await
+            .map_err(I2cError::I2c)?;
+        Ok(Packet::new(true))
+    }
+
+    async fn hard_reset<D: AsyncDelayNs>(&mut self, delay: &mut D) -> Result<bool, Self::Error> {
+        let Some(reset) = self.reset.as_mut() else {
+            return Ok(false);
+        };
+        reset.set_high().map_err(|_| I2cError::Pin)?;
+        delay.delay_ms(10).await;
+        reset.set_low().map_err(|_| I2cError::Pin)?;
+        delay.delay_ms(10).await;
+        reset.set_high().map_err(|_| I2cError::Pin)?;
+        Ok(true)
+    }
+}
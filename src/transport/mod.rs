@@ -0,0 +1,118 @@
+// SHTP framing is identical on every bus, only the way a frame is clocked
+// in and out differs. Refer to SH2-Reference-Manual 1.3 and the BNO08x
+// datasheet section 1.4 for the per-interface details.
+
+use embassy_futures::select::{Either, select};
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::InputPin;
+use embedded_hal_async::delay::DelayNs as AsyncDelayNs;
+use embedded_hal_async::digital::Wait;
+
+use crate::data::Packet;
+
+mod i2c;
+mod spi;
+mod uart;
+
+pub use i2c::{I2cTransport, NoInterrupt, NoReset};
+pub use spi::SpiTransport;
+pub use uart::UartTransport;
+
+/// Moves whole SHTP frames between the driver and the hub.
+pub trait Transport {
+    type Error;
+
+    /// Reads the next frame the hub has queued. A packet with a length of
+    /// zero means nothing was available.
+    ///
+    /// Transports with an INT line wait at most `timeout_ns` for the hub
+    /// to signal data.
+    fn read_packet<const N: usize, D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        timeout_ns: u32,
+    ) -> Result<Packet<N>, Self::Error>;
+
+    /// Writes one frame, `header` followed by `cargo`. The two go out
+    /// back to back without being copied into one buffer.
+    ///
+    /// Returns what the hub clocked out during the write. Only full duplex
+    /// buses like SPI ever fill it, the others hand back an empty packet.
+    fn write_packet<const N: usize, D: DelayNs>(
+        &mut self,
+        header: &[u8; 4],
+        cargo: &[u8],
+        delay: &mut D,
+    ) -> Result<Packet<N>, Self::Error>;
+
+    /// Limits how many bytes a single read may take, spacer included. Longer
+    /// cargo is then delivered by the hub as continuation fragments.
+    fn set_max_read(&mut self, _max_read: u16) {}
+
+    /// Pulses the reset line when the transport owns one. Returns `false`
+    /// if the hub cannot be reset from this bus.
+    fn hard_reset<D: DelayNs>(&mut self, _delay: &mut D) -> Result<bool, Self::Error> {
+        Ok(false)
+    }
+}
+
+/// [`Transport`] for the async driver, built on `embedded-hal-async`.
+#[allow(async_fn_in_trait)]
+pub trait AsyncTransport {
+    type Error;
+
+    /// See [`Transport::read_packet`].
+    async fn read_packet<const N: usize, D: AsyncDelayNs>(
+        &mut self,
+        delay: &mut D,
+        timeout_ns: u32,
+    ) -> Result<Packet<N>, Self::Error>;
+
+    /// See [`Transport::write_packet`].
+    async fn write_packet<const N: usize, D: AsyncDelayNs>(
+        &mut self,
+        header: &[u8; 4],
+        cargo: &[u8],
+        delay: &mut D,
+    ) -> Result<Packet<N>, Self::Error>;
+
+    /// See [`Transport::set_max_read`].
+    fn set_max_read(&mut self, _max_read: u16) {}
+
+    /// See [`Transport::hard_reset`].
+    async fn hard_reset<D: AsyncDelayNs>(&mut self, _delay: &mut D) -> Result<bool, Self::Error> {
+        Ok(false)
+    }
+}
+
+/// Polls INT every `poll_ns` until the hub pulls it low. Returns `false`
+/// if nothing is pending within `timeout_ns`.
+pub(crate) fn wait_for_interrupt<P: InputPin, D: DelayNs>(
+    interrupt: &mut P,
+    delay: &mut D,
+    poll_ns: u32,
+    timeout_ns: u32,
+) -> Result<bool, P::Error> {
+    let mut elapsed = 0;
+    while elapsed <= timeout_ns / poll_ns {
+        if interrupt.is_low()? {
+            return Ok(true);
+        }
+        delay.delay_ns(poll_ns);
+        elapsed += 1;
+    }
+    Ok(false)
+}
+
+/// Waits until INT is low, or gives up after `timeout_ns`. Returns at once
+/// if the hub is already holding it low.
+pub(crate) async fn wait_for_interrupt_async<P: Wait, D: AsyncDelayNs>(
+    interrupt: &mut P,
+    delay: &mut D,
+    timeout_ns: u32,
+) -> Result<bool, P::Error> {
+    match select(interrupt.wait_for_low(), delay.delay_ns(timeout_ns)).await {
+        Either::First(res) => res.map(|_| true),
+        Either::Second(_) => Ok(false),
+    }
+}
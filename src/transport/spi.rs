@@ -0,0 +1,268 @@
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{InputPin, OutputPin};
+use embedded_hal::spi::{Operation, SpiDevice};
+use embedded_hal_async::delay::DelayNs as AsyncDelayNs;
+use embedded_hal_async::digital::Wait;
+use embedded_hal_async::spi::SpiDevice as AsyncSpiDevice;
+use heapless::Vec;
+
+use crate::config::INTERRUPT_TIMEOUT_NS;
+use crate::data::Packet;
+use crate::error::SpiError;
+use crate::transport::{AsyncTransport, Transport, wait_for_interrupt, wait_for_interrupt_async};
+
+/// SHTP over SPI. A read clocks the header and the cargo in one
+/// transaction, CS staying asserted in between.
+///
+/// The hub only drives the bus after pulling INT low, so every transfer
+/// waits for it. WAKE (PS0) is pulsed before writes so a sleeping hub
+/// is ready to accept the frame. SPI is full duplex, whatever the hub
+/// sends while a frame is written is handed back from `write_packet`.
+pub struct SpiTransport<SPI, IP, WP, RP> {
+    spi: SPI,
+    interrupt: IP,
+    wake: WP,
+    reset: RP,
+    irq_time: u32,
+    max_read: u16,
+}
+
+impl<SPI, IP, WP, RP> SpiTransport<SPI, IP, WP, RP> {
+    pub fn new(spi: SPI, interrupt: IP, wake: WP, reset: RP) -> Self {
+        Self::custom_interrupt(spi, interrupt, wake, reset, 100)
+    }
+
+    /// `irq_time` is the INT polling period in nanoseconds.
+    pub fn custom_interrupt(spi: SPI, interrupt: IP, wake: WP, reset: RP, irq_time: u32) -> Self {
+        SpiTransport {
+            spi,
+            interrupt,
+            wake,
+            reset,
+            irq_time: irq_time.max(1),
+            max_read: u16::MAX,
+        }
+    }
+
+    pub fn release(self) -> (SPI, IP, WP, RP) {
+        (self.spi, self.interrupt, self.wake, self.reset)
+    }
+}
+
+impl<SPI, IP, WP, RP> SpiTransport<SPI, IP, WP, RP>
+where
+    SPI: SpiDevice,
+    IP: InputPin,
+    WP: OutputPin,
+    RP: OutputPin,
+{
+    fn wait_for_interrupt<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        timeout_ns: u32,
+    ) -> Result<bool, SpiError<SPI::Error>> {
+        wait_for_interrupt(&mut self.interrupt, delay, self.irq_time, timeout_ns)
+            .map_err(|_| SpiError::Pin)
+    }
+
+    /// Pulls WAKE low until the hub answers on INT.
+    fn send_wake<D: DelayNs>(&mut self, delay: &mut D) -> Result<(), SpiError<SPI::Error>> {
+        self.wake.set_low().map_err(|_| SpiError::Pin)?;
+        let awake = self.wait_for_interrupt(delay, INTERRUPT_TIMEOUT_NS);
+        self.wake.set_high().map_err(|_| SpiError::Pin)?;
+        if !awake? {
+            warn!("Hub didn't wake up");
+            return Err(SpiError::Timeout);
+        }
+        Ok(())
+    }
+}
+
+impl<SPI, IP, WP, RP> Transport for SpiTransport<SPI, IP, WP, RP>
+where
+    SPI: SpiDevice,
+    IP: InputPin,
+    WP: OutputPin,
+    RP: OutputPin,
+{
+    type Error = SpiError<SPI::Error>;
+
+    fn read_packet<const N: usize, D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+        timeout_ns: u32,
+    ) -> Result<Packet<N>, Self::Error> {
+        if !self.wait_for_interrupt(delay, timeout_ns)? {
+            return Ok(Packet::new(true));
+        }
+
+        let (mut header, mut cargo) = read_buffers(self.max_read);
+        self.spi
+            .transaction(&mut [
+                Operation::TransferInPlace(&mut header),
+                Operation::TransferInPlace(&mut cargo),
+            ])
+            .map_err(SpiError::Spi)?;
+        Ok(Packet::from_parts(header, cargo))
+    }
+
+    fn set_max_read(&mut self, max_read: u16) {
+        self.max_read = max_read;
+    }
+
+    fn write_packet<const N: usize, D: DelayNs>(
+        &mut self,
+        header: &[u8; 4],
+        cargo: &[u8],
+        delay: &mut D,
+    ) -> Result<Packet<N>, Self::Error> {
+        self.send_wake(delay)?;
+        let (mut rx_header, mut rx) = receive_buffers(cargo);
+        self.spi
+            .transaction(&mut [
+                Operation::Transfer(&mut rx_header, header),
+                Operation::Transfer(&mut rx, cargo),
+            ])
+            .map_err(SpiError::Spi)?;
+        Ok(Packet::from_parts(rx_header, rx))
+    }
+
+    fn hard_reset<D: DelayNs>(&mut self, delay: &mut D) -> Result<bool, Self::Error> {
+        self.wake.set_high().map_err(|_| SpiError::Pin)?;
+        self.reset.set_high().map_err(|_| SpiError::Pin)?;
+        delay.delay_ms(10);
+        self.reset.set_low().map_err(|_| SpiError::Pin)?;
+        delay.delay_ms(10);
+        self.reset.set_high().map_err(|_| SpiError::Pin)?;
+
+        // The hub pulls INT low once it is out of reset
+        if !self.wait_for_interrupt(delay, INTERRUPT_TIMEOUT_NS)? {
+            warn!("Hub didn't come out of reset");
+            return Err(SpiError::Timeout);
+        }
+        Ok(true)
+    }
+}
+
+impl<SPI, IP, WP, RP> SpiTransport<SPI, IP, WP, RP>
+where
+    SPI: AsyncSpiDevice,
+    IP: Wait,
+    WP: OutputPin,
+    RP: OutputPin,
+{
+    async fn wait_for_interrupt_async<D: AsyncDelayNs>(
+        &mut self,
+        delay: &mut D,
+        timeout_ns: u32,
+    ) -> Result<bool, SpiError<SPI::Error>> {
+        wait_for_interrupt_async(&mut self.interrupt, delay, timeout_ns)
+            .await
+            .map_err(|_| SpiError::Pin)
+    }
+
+    async fn send_wake_async<D: AsyncDelayNs>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<(), SpiError<SPI::Error>> {
+        self.wake.set_low().map_err(|_| SpiError::Pin)?;
+        let awake = self
+            .wait_for_interrupt_async(delay, INTERRUPT_TIMEOUT_NS)
+            .await;
+        self.wake.set_high().map_err(|_| SpiError::Pin)?;
+        if !awake? {
+            warn!("Hub didn't wake up");
+            return Err(SpiError::Timeout);
+        }
+        Ok(())
+    }
+}
+
+impl<SPI, IP, WP, RP> AsyncTransport for SpiTransport<SPI, IP, WP, RP>
+where
+    SPI: AsyncSpiDevice,
+    IP: Wait,
+    WP: OutputPin,
+    RP: OutputPin,
+{
+    type Error = SpiError<SPI::Error>;
+
+    async fn read_packet<const N: usize, D: AsyncDelayNs>(
+        &mut self,
+        delay: &mut D,
+        timeout_ns: u32,
+    ) -> Result<Packet<N>, Self::Error> {
+        if !self.wait_for_interrupt_async(delay, timeout_ns).await? {
+            return Ok(Packet::new(true));
+        }
+
+        let (mut header, mut cargo) = read_buffers(self.max_read);
+        self.spi
+            .transaction(&mut [
+                Operation::TransferInPlace(&mut header),
+                Operation::TransferInPlace(&mut cargo),
+            ])
+            .await
+            .map_err(SpiError::Spi)?;
+        Ok(Packet::from_parts(header, cargo))
+    }
+
+    fn set_max_read(&mut self, max_read: u16) {
+        self.max_read = max_read;
+    }
+
+    async fn write_packet<const N: usize, D: AsyncDelayNs>(
+        &mut self,
+        header: &[u8; 4],
+        cargo: &[u8],
+        delay: &mut D,
+    ) -> Result<Packet<N>, Self::Error> {
+        self.send_wake_async(delay).await?;
+        let (mut rx_header, mut rx) = receive_buffers(cargo);
+        self.spi
+            .transaction(&mut [
+                Operation::Transfer(&mut rx_header, header),
+                Operation::Transfer(&mut rx, cargo),
+            ])
+            .await
+            .map_err(SpiError::Spi)?;
+        Ok(Packet::from_parts(rx_header, rx))
+    }
+
+    async fn hard_reset<D: AsyncDelayNs>(&mut self, delay: &mut D) -> Result<bool, Self::Error> {
+        self.wake.set_high().map_err(|_| SpiError::Pin)?;
+        self.reset.set_high().map_err(|_| SpiError::Pin)?;
+        delay.delay_ms(10).await;
+        self.reset.set_low().map_err(|_| SpiError::Pin)?;
+        delay.delay_ms(10).await;
+        self.reset.set_high().map_err(|_| SpiError::Pin)?;
+
+        if !self
+            .wait_for_interrupt_async(delay, INTERRUPT_TIMEOUT_NS)
+            .await?
+        {
+            warn!("Hub didn't come out of reset");
+            return Err(SpiError::Timeout);
+        }
+        Ok(true)
+    }
+}
+
+/// Room for the largest frame a read may take. `SpiDevice` can't size an
+/// operation from data clocked in the same transaction, so the whole limit
+/// is clocked and anything past the frame is dropped as filler.
+fn read_buffers<const N: usize>(max_read: u16) -> ([u8; 4], Vec<u8, N>) {
+    let mut cargo = Vec::new();
+    cargo
+        .resize((max_read as usize).saturating_sub(4).min(N), 0)
+        .ok();
+    ([0; 4], cargo)
+}
+
+/// Room for what the hub sends during a write. Cargo past `N` bytes is
+/// clocked out but what comes back alongside it is dropped.
+fn receive_buffers<const N: usize>(cargo: &[u8]) -> ([u8; 4], Vec<u8, N>) {
+    let mut rx = Vec::new();
+    rx.resize(cargo.len().min(N), 0).ok();
+    ([0; 4], rx)
+}
@@ -0,0 +1,182 @@
+use embedded_hal::delay::DelayNs;
+use embedded_io::{Read, ReadReady, Write};
+
+use crate::config::UART_BYTE_SPACING_US;
+use crate::data::{Packet, PacketError};
+use crate::error::UartError;
+use crate::transport::Transport;
+
+const FLAG: u8 = 0x7E;
+const ESCAPE: u8 = 0x7D;
+const ESCAPE_MASK: u8 = 0x20;
+const PROTOCOL_SHTP: u8 = 0x01;
+
+/// SHTP over UART. Every frame is wrapped in 0x7E flag bytes and starts
+/// with a protocol ID. Flag and escape bytes inside the frame are sent as
+/// 0x7D followed by the byte XOR 0x20.
+///
+/// The hub can't keep up with back to back bytes, so writes are spaced by
+/// [`UART_BYTE_SPACING_US`]. Frames are returned without a spacer.
+///
+/// Reads return an empty packet while no frame has started arriving.
+/// Once one has, the rest of it is read blocking.
+pub struct UartTransport<U> {
+    uart: U,
+}
+
+impl<U> UartTransport<U>
+where
+    U: Read + ReadReady + Write,
+{
+    pub fn new(uart: U) -> Self {
+        UartTransport { uart }
+    }
+
+    pub fn release(self) -> U {
+        self.uart
+    }
+
+    /// Blocks for the next byte. The stream ending mid frame is a framing
+    /// error.
+    fn read_byte(&mut self) -> Result<u8, UartError<U::Error>> {
+        let mut byte = [0u8; 1];
+        match self.uart.read(&mut byte).map_err(UartError::Uart)? {
+            0 => Err(UartError::Frame),
+            _ => Ok(byte[0]),
+        }
+    }
+
+    /// Returns `None` when nothing has been received.
+    fn poll_byte(&mut self) -> Result<Option<u8>, UartError<U::Error>> {
+        if !self.uart.read_ready().map_err(UartError::Uart)? {
+            return Ok(None);
+        }
+        self.read_byte().map(Some)
+    }
+
+    /// Returns `None` at the closing flag.
+    fn read_unescaped(&mut self) -> Result<Option<u8>, UartError<U::Error>> {
+        match self.read_byte()? {
+            ESCAPE => Ok(Some(self.read_byte()? ^ ESCAPE_MASK)),
+            FLAG => Ok(None),
+            byte => Ok(Some(byte)),
+        }
+    }
+
+    /// Reads up to and including the closing flag.
+    fn skip_frame(&mut self) -> Result<(), UartError<U::Error>> {
+        while self.read_unescaped()?.is_some() {}
+        Ok(())
+    }
+
+    fn write_byte<D: DelayNs>(
+        &mut self,
+        byte: u8,
+        delay: &mut D,
+    ) -> Result<(), UartError<U::Error>> {
+        self.uart.write_all(&[byte]).map_err(UartError::Uart)?;
+        delay.delay_us(UART_BYTE_SPACING_US);
+        Ok(())
+    }
+
+    fn write_escaped<D: DelayNs>(
+        &mut self,
+        byte: u8,
+        delay: &mut D,
+    ) -> Result<(), UartError<U::Error>> {
+        if byte == FLAG || byte == ESCAPE {
+            self.write_byte(ESCAPE, delay)?;
+            self.write_byte(byte ^ ESCAPE_MASK, delay)
+        } else {
+            self.write_byte(byte, delay)
+        }
+    }
+
+    /// Skips ahead to the protocol ID of the next frame. Returns `None`
+    /// once no more bytes are waiting.
+    fn find_frame_start(&mut self) -> Result<Option<u8>, UartError<U::Error>> {
+        loop {
+            match self.poll_byte()? {
+                None => return Ok(None),
+                Some(FLAG) => {}
+                Some(_) => continue,
+            }
+            // The flag may have closed the previous frame, the next one
+            // then follows straight away
+            match self.poll_byte()? {
+                None => return Ok(None),
+                Some(FLAG) => match self.poll_byte()? {
+                    None => return Ok(None),
+                    Some(protocol) => return Ok(Some(protocol)),
+                },
+                Some(protocol) => return Ok(Some(protocol)),
+            }
+        }
+    }
+}
+
+impl<U> Transport for UartTransport<U>
+where
+    U: Read + ReadReady + Write,
+{
+    type Error = UartError<U::Error>;
+
+    fn read_packet<const N: usize, D: DelayNs>(
+        &mut self,
+        _delay: &mut D,
+        _timeout_ns: u32,
+    ) -> Result<Packet<N>, Self::Error> {
+        loop {
+            let Some(protocol) = self.find_frame_start()? else {
+                return Ok(Packet::new(false));
+            };
+            if protocol != PROTOCOL_SHTP {
+                // Buffer status replies and other protocols are not SHTP traffic
+                continue;
+            }
+
+            let mut header = [0u8; 4];
+            for byte in header.iter_mut() {
+                *byte = self.read_unescaped()?.ok_or(UartError::Frame)?;
+            }
+
+            let mut out = Packet::from_header(&header, false);
+            let capacity = out.as_mut_data(false).len();
+            let mut received = 0;
+            // The first fragment of a split cargo ends early, the closing
+            // flag marks how much of it was sent
+            while let Some(byte) = self.read_unescaped()? {
+                if received == capacity {
+                    // Leave the stream at the start of the next frame
+                    self.skip_frame()?;
+                    if out.data_length() as usize > capacity {
+                        return Err(UartError::Packet(PacketError::TooLarge));
+                    }
+                    return Err(UartError::Frame);
+                }
+                out.as_mut_data(false)[received] = byte;
+                received += 1;
+            }
+            out.limit_transfer(received);
+
+            return Ok(out);
+        }
+    }
+
+    fn write_packet<const N: usize, D: DelayNs>(
+        &mut self,
+        header: &[u8; 4],
+        cargo: &[u8],
+        delay: &mut D,
+    ) -> Result<Packet<N>, Self::Error> {
+        self.write_byte(FLAG, delay)?;
+        self.write_byte(PROTOCOL_SHTP, delay)?;
+        for &byte in header.iter().chain(cargo) {
+            self.write_escaped(byte, delay)?;
+        }
+        self.write_byte(FLAG, delay)?;
+
+        self.uart.flush().map_err(UartError::Uart)?;
+        Ok(Packet::new(false))
+    }
+}
@@ -0,0 +1,78 @@
+use ceva_bno08x::advertisement::{Advertisement, ChannelMap};
+
+fn tlv(out: &mut Vec<u8>, tag: u8, value: &[u8]) {
+    out.push(tag);
+    out.push(value.len() as u8);
+    out.extend_from_slice(value);
+}
+
+/// Advertisement laid out the way a BNO08x sends it after reset, with the
+/// sensor hub channels moved up by one.
+fn advertisement() -> Vec<u8> {
+    let mut cargo = vec![0x00];
+    tlv(&mut cargo, 1, &0u32.to_le_bytes());
+    tlv(&mut cargo, 2, &256u16.to_le_bytes());
+    tlv(&mut cargo, 3, &512u16.to_le_bytes());
+    tlv(&mut cargo, 4, &256u16.to_le_bytes());
+    tlv(&mut cargo, 5, &128u16.to_le_bytes());
+    tlv(&mut cargo, 8, b"SHTP\0");
+    tlv(&mut cargo, 0x80, b"1.0.1\0");
+    tlv(&mut cargo, 6, &[0]);
+    tlv(&mut cargo, 9, b"command\0");
+    tlv(&mut cargo, 1, &1u32.to_le_bytes());
+    tlv(&mut cargo, 8, b"executable\0");
+    tlv(&mut cargo, 6, &[1]);
+    tlv(&mut cargo, 9, b"device\0");
+    tlv(&mut cargo, 1, &2u32.to_le_bytes());
+    tlv(&mut cargo, 8, b"sensorhub\0");
+    tlv(&mut cargo, 0x80, b"1.0.0\0");
+    tlv(&mut cargo, 6, &[3]);
+    tlv(&mut cargo, 9, b"control\0");
+    tlv(&mut cargo, 6, &[4]);
+    tlv(&mut cargo, 9, b"inputNormal\0");
+    tlv(&mut cargo, 7, &[5]);
+    tlv(&mut cargo, 9, b"inputWake\0");
+    cargo
+}
+
+#[test]
+fn parses_limits_applications_and_channels() {
+    let adv = Advertisement::parse(&advertisement()).unwrap();
+
+    assert_eq!(adv.max_cargo_write, 256);
+    assert_eq!(adv.max_cargo_read, 512);
+    assert_eq!(adv.max_transfer_read, 128);
+    assert_eq!(adv.shtp_version(), Some("1.0.1"));
+    assert_eq!(adv.sh2_version(), Some("1.0.0"));
+    assert_eq!(adv.channel("inputNormal"), Some(4));
+    assert!(adv.channels.iter().any(|c| c.name == "inputWake" && c.wake));
+}
+
+#[test]
+fn channel_map_follows_advertisement() {
+    let adv = Advertisement::parse(&advertisement()).unwrap();
+    let map = ChannelMap::from_advertisement(&adv);
+
+    assert_eq!(map.executable, 1);
+    assert_eq!(map.control, 3);
+    assert_eq!(map.input_normal, 4);
+    assert_eq!(map.input_wake, 5);
+    // Not advertised, keeps the default
+    assert_eq!(map.input_gyro_rv, 5);
+}
+
+#[test]
+fn truncated_entry_stops_parsing() {
+    let mut cargo = advertisement();
+    cargo.truncate(cargo.len() - 3);
+
+    let adv = Advertisement::parse(&cargo).unwrap();
+
+    assert_eq!(adv.channel("inputNormal"), Some(4));
+    assert_eq!(adv.channel("inputWake"), None);
+}
+
+#[test]
+fn other_report_ids_are_rejected() {
+    assert!(Advertisement::parse(&[0xF8, 0x00]).is_none());
+}
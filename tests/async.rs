@@ -0,0 +1,253 @@
+mod common;
+
+use ceva_bno08x::SensorError;
+use ceva_bno08x::asynch::BNO08xAsync;
+use ceva_bno08x::command::{
+    CalibrationConfig, Command, CommandRequest, ErrorRecord, ErrorSource, SensorCounts, TareAxes,
+    TareBasis,
+};
+use ceva_bno08x::register::{FRSConfiguration, ReportId, Status};
+use ceva_bno08x::sim::SimulatedBno08x;
+use ceva_bno08x::transport::I2cTransport;
+use common::{ADDRESS, NoDelay, rotation_vector};
+use embassy_futures::block_on;
+
+fn initialize() -> CommandRequest {
+    CommandRequest::new(Command::Initialize, [1, 0, 0, 0, 0, 0, 0, 0, 0])
+}
+
+type Imu = BNO08xAsync<I2cTransport<SimulatedBno08x>, NoDelay>;
+
+fn new_imu(sim: SimulatedBno08x) -> Imu {
+    BNO08xAsync::new(sim, NoDelay, true)
+}
+
+fn release(imu: Imu) -> SimulatedBno08x {
+    let (transport, _) = imu.release();
+    transport.release()
+}
+
+#[test]
+fn soft_reset_waits_for_reset_complete() {
+    let mut imu = new_imu(SimulatedBno08x::new(ADDRESS));
+
+    block_on(imu.soft_reset_device()).unwrap();
+
+    assert_eq!(release(imu).resets(), 2);
+}
+
+#[test]
+fn reads_product_id() {
+    let mut imu = new_imu(SimulatedBno08x::new(ADDRESS));
+
+    assert_eq!(block_on(imu.read_product_id()).ok(), Some(true));
+}
+
+#[test]
+fn enables_dependencies_before_feature() {
+    let mut imu = new_imu(SimulatedBno08x::new(ADDRESS));
+
+    block_on(imu.enable_features(ReportId::RotationVector, Some(2500), None)).unwrap();
+
+    let sim = release(imu);
+    assert_eq!(sim.feature_interval(ReportId::RotationVector), Some(2500));
+    assert!(
+        sim.feature_interval(ReportId::AccelerometerCalibrated)
+            .is_some()
+    );
+    assert!(
+        sim.feature_interval(ReportId::GyroscopeCalibrated)
+            .is_some()
+    );
+    assert!(sim.feature_interval(ReportId::MagFieldCalibrated).is_some());
+}
+
+#[test]
+fn parses_input_reports() {
+    let mut sim = SimulatedBno08x::new(ADDRESS);
+    sim.push_report(&rotation_vector(0x2000, 0x3800));
+    let mut imu = new_imu(sim);
+    block_on(imu.enable_features(ReportId::RotationVector, None, None)).unwrap();
+
+    let (status, i, j, k, real) = block_on(imu.quaternions()).unwrap();
+
+    assert!(matches!(status, Status::HighAccuracy));
+    assert_eq!((i, j, k, real), (0.5, 0.0, 0.0, 0.875));
+}
+
+#[test]
+fn reads_frs_record() {
+    let mut sim = SimulatedBno08x::new(ADDRESS);
+    sim.set_frs_record(FRSConfiguration::SystemOrientation, &[1, 2, 3]);
+    let mut imu = new_imu(sim);
+    let mut words = [0u32; 4];
+
+    let count = block_on(imu.frs_read(FRSConfiguration::SystemOrientation, &mut words)).unwrap();
+
+    assert_eq!(count, 3);
+    assert_eq!(words, [1, 2, 3, 0]);
+}
+
+#[test]
+fn requests_are_numbered_and_answered() {
+    let mut imu = new_imu(SimulatedBno08x::new(ADDRESS));
+
+    let first = block_on(imu.command(initialize())).unwrap();
+    let second = block_on(imu.command(initialize())).unwrap();
+
+    assert_eq!((first.command, first.command_seq), (0x04, 0));
+    assert_eq!((second.command_seq, second.status()), (1, 0));
+    assert_eq!(
+        release(imu).commands()[1],
+        [0xF2, 1, 0x04, 1, 0, 0, 0, 0, 0, 0, 0, 0]
+    );
+}
+
+#[test]
+fn responses_to_other_requests_are_skipped() {
+    let mut imu = new_imu(SimulatedBno08x::new(ADDRESS));
+
+    block_on(imu.send_command(initialize())).unwrap();
+    let seq = block_on(imu.send_command(initialize())).unwrap();
+    let response = block_on(imu.wait_for_command_response(Command::Initialize, seq)).unwrap();
+
+    assert_eq!(response.command_seq, 1);
+}
+
+#[test]
+fn error_report_lists_errors_up_to_severity() {
+    let mut sim = SimulatedBno08x::new(ADDRESS);
+    sim.push_error([0, 1, 2, 0x10, 3, 0x22]);
+    sim.push_error([2, 2, 1, 0x11, 4, 0x33]);
+    let mut imu = new_imu(sim);
+
+    let errors = block_on(imu.error_report(1)).unwrap();
+    let all = block_on(imu.error_report(0xFF)).unwrap();
+
+    assert_eq!(
+        errors.as_slice(),
+        [ErrorRecord {
+            severity: 0,
+            sequence: 1,
+            source: ErrorSource::MotionHub,
+            error: 0x10,
+            module: 3,
+            code: 0x22,
+        }]
+    );
+    assert_eq!(all.len(), 2);
+    assert_eq!(all[1].source, ErrorSource::MotionEngine);
+}
+
+#[test]
+fn empty_error_report() {
+    let mut imu = new_imu(SimulatedBno08x::new(ADDRESS));
+
+    assert!(block_on(imu.error_report(0xFF)).unwrap().is_empty());
+}
+
+#[test]
+fn sensor_counts_follow_delivered_reports() {
+    let mut sim = SimulatedBno08x::new(ADDRESS);
+    sim.push_report(&rotation_vector(0x2000, 0x3800));
+    sim.push_report(&rotation_vector(0x2000, 0x3800));
+    let mut imu = new_imu(sim);
+    block_on(imu.enable_features(ReportId::RotationVector, None, None)).unwrap();
+    block_on(imu.quaternions()).unwrap();
+    block_on(imu.quaternions()).unwrap();
+
+    let rotation = block_on(imu.sensor_counts(ReportId::RotationVector)).unwrap();
+    block_on(imu.clear_sensor_counts(ReportId::RotationVector)).unwrap();
+    let cleared = block_on(imu.sensor_counts(ReportId::RotationVector)).unwrap();
+
+    assert_eq!(
+        rotation,
+        SensorCounts {
+            offered: 2,
+            produced: 2,
+            accepted: 2,
+            attained: 2
+        }
+    );
+    assert_eq!(cleared, SensorCounts::default());
+}
+
+#[test]
+fn tare_requests_are_laid_out_per_manual() {
+    let mut imu = new_imu(SimulatedBno08x::new(ADDRESS));
+
+    block_on(imu.tare_now(TareAxes::Z, TareBasis::GameRotationVector)).unwrap();
+    block_on(imu.persist_tare()).unwrap();
+    block_on(imu.set_reorientation(0.0, 0.0, -0.5, 0.875)).unwrap();
+
+    let sim = release(imu);
+    let params: Vec<_> = sim.commands().iter().map(|c| (c[2], &c[3..])).collect();
+    assert_eq!(
+        params,
+        [
+            (0x03, &[0, 0b100, 1, 0, 0, 0, 0, 0, 0][..]),
+            (0x03, &[1, 0, 0, 0, 0, 0, 0, 0, 0][..]),
+            (0x03, &[2, 0, 0, 0, 0, 0x00, 0xE0, 0x00, 0x38][..]),
+        ]
+    );
+}
+
+#[test]
+fn dcd_save_and_autosave() {
+    let mut imu = new_imu(SimulatedBno08x::new(ADDRESS));
+
+    block_on(imu.save_dcd()).unwrap();
+    block_on(imu.set_dcd_autosave(false)).unwrap();
+    // Periodic save isn't answered
+    assert_eq!(block_on(imu.read_packet()).unwrap().packet_length(), 0);
+
+    let sim = release(imu);
+    assert_eq!(sim.dcd_saves(), 1);
+    assert!(!sim.dcd_autosave());
+}
+
+#[test]
+fn failed_dcd_save_is_an_error() {
+    let mut sim = SimulatedBno08x::new(ADDRESS);
+    sim.set_dcd_status(3);
+    let mut imu = new_imu(sim);
+
+    assert!(matches!(
+        block_on(imu.save_dcd()),
+        Err(SensorError::CommandFailed {
+            command: Command::SaveDcd,
+            status: 3
+        })
+    ));
+}
+
+#[test]
+fn clear_dcd_resets_and_restores_features() {
+    let mut imu = new_imu(SimulatedBno08x::new(ADDRESS));
+    block_on(imu.enable_features(ReportId::RotationVector, Some(5000), None)).unwrap();
+
+    block_on(imu.clear_dcd_and_reset()).unwrap();
+
+    assert!(!imu.take_reset_event());
+    let sim = release(imu);
+    assert_eq!(sim.resets(), 2);
+    assert_eq!(sim.feature_interval(ReportId::RotationVector), Some(5000));
+}
+
+#[test]
+fn calibration_config_round_trip() {
+    let mut imu = new_imu(SimulatedBno08x::new(ADDRESS));
+    let no_mag = CalibrationConfig {
+        accel: true,
+        gyro: true,
+        mag: false,
+        planar: true,
+        on_table: true,
+    };
+
+    assert!(block_on(imu.calibration_config()).unwrap().mag);
+    block_on(imu.set_calibration_config(no_mag)).unwrap();
+
+    assert_eq!(block_on(imu.calibration_config()).unwrap(), no_mag);
+    assert_eq!(release(imu).calibration_config(), no_mag);
+}
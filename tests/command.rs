@@ -0,0 +1,241 @@
+mod common;
+
+use ceva_bno08x::command::{
+    CalibrationConfig, Command, CommandRequest, CommandResponse, ErrorRecord, ErrorSource,
+    SensorCounts, TareAxes, TareBasis,
+};
+use ceva_bno08x::register::ReportId;
+use ceva_bno08x::sim::SimulatedBno08x;
+use ceva_bno08x::{BNO08x, SensorError};
+use common::{ADDRESS, NoDelay, rotation_vector};
+
+fn initialize() -> CommandRequest {
+    CommandRequest::new(Command::Initialize, [1, 0, 0, 0, 0, 0, 0, 0, 0])
+}
+
+#[test]
+fn requests_are_numbered_and_answered() {
+    let mut imu = BNO08x::new(SimulatedBno08x::new(ADDRESS), NoDelay, true);
+
+    let first = imu.command(initialize()).unwrap();
+    let second = imu.command(initialize()).unwrap();
+
+    assert_eq!((first.command, first.command_seq), (0x04, 0));
+    assert_eq!((second.command_seq, second.status()), (1, 0));
+    assert_eq!(second.fields[1], 1);
+    let (transport, _) = imu.release();
+    let sim = transport.release();
+    assert_eq!(
+        sim.commands()[1],
+        [0xF2, 1, 0x04, 1, 0, 0, 0, 0, 0, 0, 0, 0]
+    );
+}
+
+#[test]
+fn responses_to_other_requests_are_skipped() {
+    let mut imu = BNO08x::new(SimulatedBno08x::new(ADDRESS), NoDelay, true);
+
+    imu.send_command(initialize()).unwrap();
+    let seq = imu.send_command(initialize()).unwrap();
+    let response = imu
+        .wait_for_command_response(Command::Initialize, seq)
+        .unwrap();
+
+    assert_eq!(response.command_seq, 1);
+}
+
+#[test]
+fn unsolicited_responses_are_flagged() {
+    let mut cargo = [0u8; 16];
+    cargo[..5].copy_from_slice(&[0xF1, 7, 0x84, 0, 0]);
+
+    let response = CommandResponse::parse(&cargo).unwrap();
+
+    assert!(response.unsolicited);
+    assert_eq!(response.command, Command::Initialize as u8);
+    assert!(!response.answers(Command::Initialize, 0));
+    assert!(CommandResponse::parse(&cargo[..15]).is_none());
+}
+
+#[test]
+fn error_report_lists_errors_up_to_severity() {
+    let mut sim = SimulatedBno08x::new(ADDRESS);
+    sim.push_error([0, 1, 2, 0x10, 3, 0x22]);
+    sim.push_error([2, 2, 1, 0x11, 4, 0x33]);
+    let mut imu = BNO08x::new(sim, NoDelay, true);
+
+    let errors = imu.error_report(1).unwrap();
+    let all = imu.error_report(0xFF).unwrap();
+
+    assert_eq!(
+        errors.as_slice(),
+        [ErrorRecord {
+            severity: 0,
+            sequence: 1,
+            source: ErrorSource::MotionHub,
+            error: 0x10,
+            module: 3,
+            code: 0x22,
+        }]
+    );
+    assert_eq!(all.len(), 2);
+    assert_eq!(all[1].source, ErrorSource::MotionEngine);
+}
+
+#[test]
+fn empty_error_report() {
+    let mut imu = BNO08x::new(SimulatedBno08x::new(ADDRESS), NoDelay, true);
+
+    assert!(imu.error_report(0xFF).unwrap().is_empty());
+}
+
+#[test]
+fn sensor_counts_follow_delivered_reports() {
+    let mut sim = SimulatedBno08x::new(ADDRESS);
+    let report = rotation_vector(0, 0);
+    sim.push_report(&report);
+    sim.push_report(&[
+        ReportId::GameRotationVector as u8,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+    ]);
+    sim.push_report(&report);
+    let mut imu = BNO08x::new(sim, NoDelay, true);
+    imu.enable_features(ReportId::RotationVector, None, None)
+        .unwrap();
+    imu.quaternions().unwrap();
+    imu.quaternions().unwrap();
+
+    let rotation = imu.sensor_counts(ReportId::RotationVector).unwrap();
+    let game = imu.sensor_counts(ReportId::GameRotationVector).unwrap();
+    imu.clear_sensor_counts(ReportId::RotationVector).unwrap();
+    let cleared = imu.sensor_counts(ReportId::RotationVector).unwrap();
+
+    assert_eq!(
+        rotation,
+        SensorCounts {
+            offered: 2,
+            produced: 2,
+            accepted: 2,
+            attained: 2
+        }
+    );
+    assert_eq!((game.offered, game.attained), (1, 0));
+    assert_eq!(cleared, SensorCounts::default());
+}
+
+#[test]
+fn tare_requests_are_laid_out_per_manual() {
+    let mut imu = BNO08x::new(SimulatedBno08x::new(ADDRESS), NoDelay, true);
+
+    imu.tare_now(TareAxes::Z, TareBasis::GameRotationVector)
+        .unwrap();
+    imu.tare_now(TareAxes::X | TareAxes::Y, TareBasis::RotationVector)
+        .unwrap();
+    imu.persist_tare().unwrap();
+    imu.set_reorientation(0.0, 0.0, -0.5, 0.875).unwrap();
+
+    let (transport, _) = imu.release();
+    let sim = transport.release();
+    let params: Vec<_> = sim.commands().iter().map(|c| (c[2], &c[3..])).collect();
+    assert_eq!(
+        params,
+        [
+            (0x03, &[0, 0b100, 1, 0, 0, 0, 0, 0, 0][..]),
+            (0x03, &[0, 0b011, 0, 0, 0, 0, 0, 0, 0][..]),
+            (0x03, &[1, 0, 0, 0, 0, 0, 0, 0, 0][..]),
+            (0x03, &[2, 0, 0, 0, 0, 0x00, 0xE0, 0x00, 0x38][..]),
+        ]
+    );
+}
+
+#[test]
+fn dcd_save_and_autosave() {
+    let mut imu = BNO08x::new(SimulatedBno08x::new(ADDRESS), NoDelay, true);
+
+    imu.save_dcd().unwrap();
+    imu.set_dcd_autosave(false).unwrap();
+    // Periodic save isn't answered
+    assert_eq!(imu.read_packet().unwrap().packet_length(), 0);
+
+    let (transport, _) = imu.release();
+    let sim = transport.release();
+    assert_eq!(sim.dcd_saves(), 1);
+    assert!(!sim.dcd_autosave());
+    assert_eq!(sim.commands()[1][2..4], [0x09, 1]);
+}
+
+#[test]
+fn stray_response_after_autosave_is_skipped() {
+    let mut sim = SimulatedBno08x::new(ADDRESS);
+    // A response to the periodic save the manual says never comes
+    let mut stray = [0u8; 16];
+    stray[..3].copy_from_slice(&[0xF1, 0, 0x09]);
+    sim.push_control(&stray);
+    let mut imu = BNO08x::new(sim, NoDelay, true);
+
+    imu.set_dcd_autosave(false).unwrap();
+    let response = imu.command(initialize()).unwrap();
+
+    assert_eq!((response.command, response.command_seq), (0x04, 1));
+}
+
+#[test]
+fn failed_dcd_save_is_an_error() {
+    let mut sim = SimulatedBno08x::new(ADDRESS);
+    sim.set_dcd_status(3);
+    let mut imu = BNO08x::new(sim, NoDelay, true);
+
+    assert!(matches!(
+        imu.save_dcd(),
+        Err(SensorError::CommandFailed {
+            command: Command::SaveDcd,
+            status: 3
+        })
+    ));
+}
+
+#[test]
+fn clear_dcd_resets_and_restores_features() {
+    let mut imu = BNO08x::new(SimulatedBno08x::new(ADDRESS), NoDelay, true);
+    imu.enable_features(ReportId::RotationVector, Some(5000), None)
+        .unwrap();
+
+    imu.clear_dcd_and_reset().unwrap();
+
+    assert!(!imu.take_reset_event());
+    let (transport, _) = imu.release();
+    let sim = transport.release();
+    assert_eq!(sim.resets(), 2);
+    assert_eq!(sim.feature_interval(ReportId::RotationVector), Some(5000));
+}
+
+#[test]
+fn calibration_config_round_trip() {
+    let mut imu = BNO08x::new(SimulatedBno08x::new(ADDRESS), NoDelay, true);
+    let no_mag = CalibrationConfig {
+        accel: true,
+        gyro: true,
+        mag: false,
+        planar: true,
+        on_table: true,
+    };
+
+    assert!(imu.calibration_config().unwrap().mag);
+    imu.set_calibration_config(no_mag).unwrap();
+
+    assert_eq!(imu.calibration_config().unwrap(), no_mag);
+    let (transport, _) = imu.release();
+    let sim = transport.release();
+    assert_eq!(sim.commands()[1][2..9], [0x07, 1, 1, 0, 0, 1, 1]);
+    assert_eq!(sim.calibration_config(), no_mag);
+}
@@ -0,0 +1,86 @@
+#![allow(dead_code)]
+
+use std::collections::VecDeque;
+use std::convert::Infallible;
+
+use ceva_bno08x::register::ReportId;
+use embedded_hal::delay::DelayNs;
+
+/// Address the simulated hubs answer on.
+pub const ADDRESS: u8 = 0x4A;
+
+/// A rotation vector input report at high accuracy. Only `i` and the real
+/// part are set, as raw Q14 values.
+pub fn rotation_vector(i: i16, real: i16) -> [u8; 14] {
+    let mut report = [0u8; 14];
+    report[0] = ReportId::RotationVector as u8;
+    report[2] = 0x03; // High accuracy
+    report[4..6].copy_from_slice(&i.to_le_bytes());
+    report[10..12].copy_from_slice(&real.to_le_bytes());
+    report
+}
+
+/// Plays back canned UART-SHTP frames and records what gets written.
+#[derive(Default)]
+pub struct Script {
+    pub bytes: VecDeque<u8>,
+    pub written: Vec<u8>,
+}
+
+impl Script {
+    pub fn frame(&mut self, length: u16, channel: u8, seq: u8, cargo: &[u8]) {
+        self.bytes.extend([0x7E, 0x01]);
+        self.bytes.extend(length.to_le_bytes());
+        self.bytes.extend([channel, seq]);
+        self.bytes.extend(cargo);
+        self.bytes.push_back(0x7E);
+    }
+
+    /// Queues a complete, unfragmented packet.
+    pub fn packet(&mut self, channel: u8, seq: u8, cargo: &[u8]) {
+        self.frame(cargo.len() as u16 + 4, channel, seq, cargo);
+    }
+}
+
+impl embedded_io::ErrorType for Script {
+    type Error = Infallible;
+}
+
+impl embedded_io::Read for Script {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        match (buf.first_mut(), self.bytes.pop_front()) {
+            (Some(slot), Some(byte)) => {
+                *slot = byte;
+                Ok(1)
+            }
+            _ => Ok(0),
+        }
+    }
+}
+
+impl embedded_io::ReadReady for Script {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(!self.bytes.is_empty())
+    }
+}
+
+impl embedded_io::Write for Script {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.written.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+pub struct NoDelay;
+
+impl DelayNs for NoDelay {
+    fn delay_ns(&mut self, _ns: u32) {}
+}
+
+impl embedded_hal_async::delay::DelayNs for NoDelay {
+    async fn delay_ns(&mut self, _ns: u32) {}
+}
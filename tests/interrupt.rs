@@ -0,0 +1,158 @@
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::rc::Rc;
+
+use ceva_bno08x::data::Packet;
+use ceva_bno08x::error::I2cError;
+use ceva_bno08x::transport::{I2cTransport, Transport};
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{ErrorType as PinErrorType, InputPin};
+use embedded_hal::i2c::{ErrorType, I2c, Operation};
+
+/// Hands out queued transfers, header first, and counts bus reads.
+#[derive(Default)]
+struct Bus {
+    queue: VecDeque<Vec<u8>>,
+    reads: usize,
+}
+
+impl Bus {
+    fn send(&mut self, channel: u8, seq: u8, cargo: &[u8]) {
+        let mut frame = Vec::new();
+        frame.extend((cargo.len() as u16 + 4).to_le_bytes());
+        frame.extend([channel, seq]);
+        frame.extend(cargo);
+        self.queue.push_back(frame);
+    }
+}
+
+impl ErrorType for Bus {
+    type Error = Infallible;
+}
+
+impl I2c for Bus {
+    fn transaction(
+        &mut self,
+        _address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        for operation in operations {
+            if let Operation::Read(buf) = operation {
+                self.reads += 1;
+                buf.fill(0);
+                if let Some(frame) = self.queue.front() {
+                    let len = buf.len().min(frame.len());
+                    buf[..len].copy_from_slice(&frame[..len]);
+                    if buf.len() > 4 {
+                        self.queue.pop_front();
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// INT line, low while the hub has data queued.
+#[derive(Clone, Default)]
+struct Int(Rc<Cell<bool>>);
+
+impl PinErrorType for Int {
+    type Error = Infallible;
+}
+
+impl InputPin for Int {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(!self.0.get())
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.0.get())
+    }
+}
+
+#[derive(Default)]
+struct CountingDelay {
+    ns: u64,
+}
+
+impl DelayNs for CountingDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        self.ns += ns as u64;
+    }
+}
+
+#[test]
+fn bus_is_left_alone_until_int_asserts() {
+    let mut bus = Bus::default();
+    bus.send(3, 0, &[0xFB, 0, 0, 0, 0]);
+    let int = Int::default();
+    let mut transport = I2cTransport::with_interrupt(bus, 0x4A, int.clone());
+    let mut delay = CountingDelay::default();
+
+    let idle: Packet = transport.read_packet(&mut delay, 300_000_000).unwrap();
+    assert_eq!(idle.packet_length(), 0);
+
+    int.0.set(true);
+    let out: Packet = transport.read_packet(&mut delay, 300_000_000).unwrap();
+    assert_eq!((out.channel(), out.data_length()), (3, 5));
+
+    let (bus, _) = transport.release_with_interrupt();
+    assert_eq!(bus.reads, 2);
+}
+
+#[test]
+fn data_ready_reads_skip_the_polling_delay() {
+    let mut bus = Bus::default();
+    bus.send(3, 0, &[0xFB, 0, 0, 0, 0]);
+    let mut transport = I2cTransport::with_interrupt(bus, 0x4A, Int(Rc::new(Cell::new(true))));
+    let mut delay = CountingDelay::default();
+
+    let _: Packet = transport.read_packet(&mut delay, 300_000_000).unwrap();
+
+    assert_eq!(delay.ns, 0);
+}
+
+#[test]
+fn polling_without_int_still_waits() {
+    let mut bus = Bus::default();
+    bus.send(3, 0, &[0xFB, 0, 0, 0, 0]);
+    let mut transport = I2cTransport::new(bus, 0x4A);
+    let mut delay = CountingDelay::default();
+
+    let out: Packet = transport.read_packet(&mut delay, 300_000_000).unwrap();
+
+    assert_eq!(out.data_length(), 5);
+    assert_eq!(delay.ns, 5_000_000);
+}
+
+/// An INT line whose reads always fail.
+struct BrokenInt;
+
+impl PinErrorType for BrokenInt {
+    type Error = embedded_hal::digital::ErrorKind;
+}
+
+impl InputPin for BrokenInt {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Err(embedded_hal::digital::ErrorKind::Other)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Err(embedded_hal::digital::ErrorKind::Other)
+    }
+}
+
+#[test]
+fn int_pin_errors_are_reported() {
+    let mut bus = Bus::default();
+    bus.send(3, 0, &[0xFB, 0, 0, 0, 0]);
+    let mut transport = I2cTransport::with_interrupt(bus, 0x4A, BrokenInt);
+
+    let out: Result<Packet, _> = transport.read_packet(&mut CountingDelay::default(), 300_000_000);
+
+    assert!(matches!(out, Err(I2cError::Pin)));
+    let (bus, _) = transport.release_with_interrupt();
+    assert_eq!(bus.reads, 0);
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use ceva_bno08x::BNO08x;
+use ceva_bno08x::SensorError;
+use ceva_bno08x::data::{Packet, PacketError};
+use ceva_bno08x::transport::UartTransport;
+use common::{NoDelay, Script};
+
+#[test]
+fn cargo_beyond_capacity_is_rejected() {
+    let packet = Packet::<16>::from_data_buf(&[0; 17], 2, 0, false);
+
+    assert!(matches!(packet, Err(PacketError::TooLarge)));
+}
+
+#[test]
+fn header_and_cargo_are_split_without_spacer() {
+    let packet: Packet = Packet::from_buf(&[6, 0, 2, 1, 6, 0, 2, 1, 0xF8, 0x00], true).unwrap();
+
+    assert_eq!(packet.header(), &[6, 0, 2, 1]);
+    assert_eq!(packet.cargo(), &[0xF8, 0x00]);
+}
+
+#[test]
+fn small_capacity_drops_oversized_cargo() {
+    let mut script = Script::default();
+    script.frame(24, 2, 0, &[0xF3; 10]);
+    script.frame(0x8000 | 14, 2, 1, &[0x00; 10]);
+    script.packet(2, 2, &[0xF8, 0x00]);
+    let mut imu = BNO08x::<_, _, 16>::from_transport(UartTransport::new(script), NoDelay);
+
+    assert!(matches!(
+        imu.read_packet(),
+        Err(SensorError::Packet(PacketError::TooLarge))
+    ));
+    assert_eq!(imu.read_packet().unwrap().report_id(), 0xF8);
+}
+
+#[test]
+fn buffer_shorter_than_header_is_rejected() {
+    let packet = Packet::<16>::from_buf(&[6, 0, 2], false);
+
+    assert!(matches!(packet, Err(PacketError::Truncated)));
+}
+
+#[test]
+fn truncated_input_report_is_dropped() {
+    let mut script = Script::default();
+    script.packet(3, 0, &[0xFB, 0, 0, 0, 0, 0x05, 0, 0, 0]);
+    let mut rotation = [0u8; 14];
+    rotation[0] = 0x05;
+    rotation[10..12].copy_from_slice(&0x4000i16.to_le_bytes());
+    let mut cargo = vec![0xFB, 0, 0, 0, 0];
+    cargo.extend(rotation);
+    // A whole report, then one cut off after its ID
+    cargo.push(0x05);
+    script.packet(3, 1, &cargo);
+    let mut imu: BNO08x<_, _> = BNO08x::new_uart(script, NoDelay);
+
+    imu.update_sensors().unwrap();
+    let (_, _, _, _, real) = imu.quaternions().unwrap();
+
+    assert_eq!(real, 1.0);
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use ceva_bno08x::BNO08x;
+use ceva_bno08x::SensorError;
+use ceva_bno08x::data::PacketError;
+use common::{NoDelay, Script};
+
+#[test]
+fn continuation_fragments_are_joined() {
+    let mut script = Script::default();
+    // 6 byte cargo split 2 + 4, with a channel 3 packet in between
+    script.frame(10, 2, 4, &[0xF3, 0x01]);
+    script.frame(5, 3, 0, &[0xAA]);
+    script.frame(0x8000 | 8, 2, 5, &[0x02, 0x03, 0x04, 0x05]);
+    let mut imu = BNO08x::new_uart(script, NoDelay);
+
+    let mut interleaved = imu.read_packet().unwrap();
+    assert_eq!(interleaved.channel(), 3);
+    assert_eq!(interleaved.as_mut_data(false), &[0xAA]);
+
+    let mut joined = imu.read_packet().unwrap();
+    assert_eq!(joined.channel(), 2);
+    assert_eq!(joined.data_length(), 6);
+    assert_eq!(
+        joined.as_mut_data(false),
+        &[0xF3, 0x01, 0x02, 0x03, 0x04, 0x05]
+    );
+}
+
+#[test]
+fn gap_in_fragments_is_reported() {
+    let mut script = Script::default();
+    script.frame(10, 2, 4, &[0xF3, 0x01]);
+    // Sequence 5 went missing
+    script.frame(0x8000 | 6, 2, 6, &[0x04, 0x05]);
+    let mut imu = BNO08x::new_uart(script, NoDelay);
+
+    assert!(matches!(
+        imu.read_packet(),
+        Err(SensorError::Packet(PacketError::MissingFragment))
+    ));
+}
+
+#[test]
+fn new_cargo_before_last_fragment_is_reported_and_kept() {
+    let mut script = Script::default();
+    script.frame(10, 2, 4, &[0xF3, 0x01]);
+    script.frame(6, 2, 5, &[0xF8, 0x00]);
+    let mut imu = BNO08x::new_uart(script, NoDelay);
+
+    assert!(matches!(
+        imu.read_packet(),
+        Err(SensorError::Packet(PacketError::MissingFragment))
+    ));
+    assert_eq!(imu.read_packet().unwrap().report_id(), 0xF8);
+}
@@ -0,0 +1,270 @@
+mod common;
+
+use std::cell::{Cell, RefCell};
+use std::convert::Infallible;
+use std::rc::Rc;
+
+use ceva_bno08x::register::ReportId;
+use ceva_bno08x::sim::{SimError, SimulatedBno08x};
+use ceva_bno08x::{BNO08x, SensorError};
+use common::{ADDRESS, NoDelay, rotation_vector};
+use embedded_hal::digital::{ErrorType as PinErrorType, OutputPin};
+use embedded_hal::i2c::{ErrorType, I2c, Operation};
+
+/// The simulated hub with its NRST line brought out.
+#[derive(Clone)]
+struct Wired(Rc<RefCell<SimulatedBno08x>>);
+
+impl ErrorType for Wired {
+    type Error = SimError;
+}
+
+impl I2c for Wired {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.0.borrow_mut().transaction(address, operations)
+    }
+}
+
+/// The simulated hub that hangs in its bootloader after a reset and stops
+/// acknowledging the bus.
+struct Stuck {
+    hub: Wired,
+    booting: Rc<Cell<bool>>,
+}
+
+impl ErrorType for Stuck {
+    type Error = SimError;
+}
+
+impl I2c for Stuck {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        if self.booting.get() {
+            return Err(SimError::Nack);
+        }
+        let resets = self.hub.0.borrow().resets();
+        let out = self.hub.transaction(address, operations);
+        self.booting.set(self.hub.0.borrow().resets() > resets);
+        out
+    }
+}
+
+/// The simulated hub behind a bus that NACKs the next `failures` writes.
+struct Flaky {
+    hub: Wired,
+    failures: Rc<Cell<u32>>,
+}
+
+impl ErrorType for Flaky {
+    type Error = SimError;
+}
+
+impl I2c for Flaky {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let writes = operations
+            .iter()
+            .any(|operation| matches!(operation, Operation::Write(_)));
+        if writes && self.failures.get() > 0 {
+            self.failures.set(self.failures.get() - 1);
+            return Err(SimError::Nack);
+        }
+        self.hub.transaction(address, operations)
+    }
+}
+
+/// Resets the hub when released after being held low.
+struct Nrst {
+    hub: Wired,
+    levels: Vec<bool>,
+}
+
+impl PinErrorType for Nrst {
+    type Error = Infallible;
+}
+
+impl OutputPin for Nrst {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.levels.push(false);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        if self.levels.last() == Some(&false) {
+            self.hub.0.borrow_mut().reset();
+        }
+        self.levels.push(true);
+        Ok(())
+    }
+}
+
+#[test]
+fn hard_reset_restores_features() {
+    let hub = Wired(Rc::new(RefCell::new(SimulatedBno08x::new(ADDRESS))));
+    let nrst = Nrst {
+        hub: hub.clone(),
+        levels: Vec::new(),
+    };
+    let mut imu = BNO08x::new_with_reset(hub.clone(), nrst, NoDelay, true);
+    imu.enable_features(ReportId::RotationVector, Some(5000), None)
+        .unwrap();
+
+    imu.hard_reset_device().unwrap();
+
+    let sim = hub.0.borrow();
+    assert_eq!(sim.resets(), 2);
+    assert_eq!(sim.feature_interval(ReportId::RotationVector), Some(5000));
+    assert!(sim.feature_interval(ReportId::MagFieldCalibrated).is_some());
+    drop(sim);
+    let (transport, _) = imu.release();
+    let (_, _, nrst) = transport.release_with_pins();
+    assert_eq!(nrst.unwrap().levels, [true, false, true]);
+}
+
+#[test]
+fn unsolicited_reset_is_reported_and_features_restored() {
+    let hub = Wired(Rc::new(RefCell::new(SimulatedBno08x::new(ADDRESS))));
+    let mut imu: BNO08x<_, _> = BNO08x::new(hub.clone(), NoDelay, true);
+    imu.enable_features(ReportId::RotationVector, Some(5000), None)
+        .unwrap();
+    assert!(!imu.take_reset_event());
+
+    // Brownout, nobody asked for this one
+    hub.0.borrow_mut().reset();
+    imu.update_sensors().unwrap();
+    // The restore goes out ahead of the next read
+    imu.update_sensors().unwrap();
+
+    assert!(imu.take_reset_event());
+    assert!(!imu.take_reset_event());
+    let sim = hub.0.borrow();
+    assert_eq!(sim.feature_interval(ReportId::RotationVector), Some(5000));
+    assert!(sim.feature_interval(ReportId::MagFieldCalibrated).is_some());
+}
+
+#[test]
+fn reset_of_an_already_running_hub_is_reported() {
+    let hub = Wired(Rc::new(RefCell::new(SimulatedBno08x::new(ADDRESS))));
+    // The hub booted long before the driver, its startup messages are gone
+    for _ in 0..3 {
+        hub.0.borrow_mut().read(ADDRESS, &mut [0; 256]).unwrap();
+    }
+    let mut imu: BNO08x<_, _> = BNO08x::new(hub.clone(), NoDelay, true);
+    imu.enable_features(ReportId::RotationVector, Some(5000), None)
+        .unwrap();
+
+    hub.0.borrow_mut().reset();
+    imu.update_sensors().unwrap();
+    imu.update_sensors().unwrap();
+
+    assert!(imu.take_reset_event());
+    let sim = hub.0.borrow();
+    assert_eq!(sim.feature_interval(ReportId::RotationVector), Some(5000));
+}
+
+#[test]
+fn hard_reset_needs_a_reset_pin() {
+    let mut imu = BNO08x::new(SimulatedBno08x::new(ADDRESS), NoDelay, true);
+
+    assert!(matches!(
+        imu.hard_reset_device(),
+        Err(SensorError::Unimplemented)
+    ));
+}
+
+#[test]
+fn sleep_and_wake_round_trip() {
+    let hub = Wired(Rc::new(RefCell::new(SimulatedBno08x::new(ADDRESS))));
+    let mut imu: BNO08x<_, _> = BNO08x::new(hub.clone(), NoDelay, true);
+    imu.enable_features(ReportId::RotationVector, Some(5000), None)
+        .unwrap();
+    let report = rotation_vector(0, 0);
+
+    imu.sleep_device().unwrap();
+    assert!(hub.0.borrow().is_asleep());
+    hub.0.borrow_mut().push_report(&report);
+    assert!(!imu.update_sensors().unwrap());
+
+    imu.wake_device().unwrap();
+    assert!(!hub.0.borrow().is_asleep());
+    assert!(imu.update_sensors().unwrap());
+}
+
+#[test]
+fn missing_reset_complete_times_out() {
+    let hub = Wired(Rc::new(RefCell::new(SimulatedBno08x::new(ADDRESS))));
+    let booting = Rc::new(Cell::new(false));
+    let stuck = Stuck {
+        hub: hub.clone(),
+        booting: booting.clone(),
+    };
+    let mut imu: BNO08x<_, _> = BNO08x::new(stuck, NoDelay, true);
+
+    assert!(matches!(
+        imu.soft_reset_device(),
+        Err(SensorError::Timeout {
+            channel: 1,
+            report_id: Some(0x01)
+        })
+    ));
+    assert!(booting.get());
+    assert_eq!(hub.0.borrow().resets(), 2);
+}
+
+#[test]
+fn soft_reset_forgets_features() {
+    let hub = Wired(Rc::new(RefCell::new(SimulatedBno08x::new(ADDRESS))));
+    let nrst = Nrst {
+        hub: hub.clone(),
+        levels: Vec::new(),
+    };
+    let mut imu = BNO08x::new_with_reset(hub.clone(), nrst, NoDelay, true);
+    imu.enable_features(ReportId::RotationVector, Some(5000), None)
+        .unwrap();
+
+    imu.soft_reset_device().unwrap();
+    imu.hard_reset_device().unwrap();
+
+    let sim = hub.0.borrow();
+    assert_eq!(sim.resets(), 3);
+    assert_eq!(sim.feature_interval(ReportId::RotationVector), None);
+    assert_eq!(sim.feature_interval(ReportId::MagFieldCalibrated), None);
+}
+
+#[test]
+fn failed_restore_is_retried() {
+    let hub = Wired(Rc::new(RefCell::new(SimulatedBno08x::new(ADDRESS))));
+    let failures = Rc::new(Cell::new(0));
+    let flaky = Flaky {
+        hub: hub.clone(),
+        failures: failures.clone(),
+    };
+    let mut imu: BNO08x<_, _> = BNO08x::new(flaky, NoDelay, true);
+    imu.enable_features(ReportId::RotationVector, Some(5000), None)
+        .unwrap();
+
+    hub.0.borrow_mut().reset();
+    imu.update_sensors().unwrap();
+    failures.set(1);
+    assert!(matches!(imu.update_sensors(), Err(SensorError::Bus(_))));
+    assert_eq!(
+        hub.0.borrow().feature_interval(ReportId::RotationVector),
+        None
+    );
+
+    imu.update_sensors().unwrap();
+    assert_eq!(
+        hub.0.borrow().feature_interval(ReportId::RotationVector),
+        Some(5000)
+    );
+}
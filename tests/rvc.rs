@@ -0,0 +1,61 @@
+use ceva_bno08x::rvc::{RvcError, RvcParser};
+
+fn frame(index: u8, yaw: i16, accel_z: i16) -> [u8; 19] {
+    let mut buf = [0u8; 19];
+    buf[0] = 0xAA;
+    buf[1] = 0xAA;
+    buf[2] = index;
+    buf[3..5].copy_from_slice(&yaw.to_le_bytes());
+    buf[13..15].copy_from_slice(&accel_z.to_le_bytes());
+    buf[18] = buf[2..18]
+        .iter()
+        .fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+    buf
+}
+
+fn feed(parser: &mut RvcParser, bytes: &[u8]) -> Vec<Result<ceva_bno08x::rvc::RvcFrame, RvcError>> {
+    bytes.iter().filter_map(|byte| parser.push(*byte)).collect()
+}
+
+#[test]
+fn decodes_frame_after_leading_noise() {
+    let mut parser = RvcParser::new();
+    let mut stream = vec![0x12, 0xAA, 0x34];
+    stream.extend(frame(5, -9000, 1000));
+
+    let frames = feed(&mut parser, &stream);
+
+    assert_eq!(frames.len(), 1);
+    let decoded = frames[0].unwrap();
+    assert_eq!(decoded.index, 5);
+    assert!((decoded.yaw + 90.0).abs() < 1e-3);
+    assert!((decoded.acceleration.2 - 9.80665).abs() < 1e-3);
+}
+
+#[test]
+fn bad_checksum_resyncs_on_next_header() {
+    let mut parser = RvcParser::new();
+    let mut corrupt = frame(1, 100, 0);
+    corrupt[18] ^= 0xFF;
+    // Cut the corrupt frame short so the next header lands inside it
+    let mut stream = corrupt[..10].to_vec();
+    stream.extend(frame(2, 200, 0));
+    stream.extend([0u8; 9]);
+    stream.extend(frame(3, 300, 0));
+
+    let frames = feed(&mut parser, &stream);
+
+    assert_eq!(frames.first(), Some(&Err(RvcError::Checksum)));
+    assert_eq!(frames.last().unwrap().unwrap().index, 3);
+    assert!(frames.iter().any(|f| f.map(|f| f.index) == Ok(2)));
+}
+
+#[test]
+fn read_frame_stops_when_stream_is_dry() {
+    let mut parser = RvcParser::new();
+    let stream = frame(9, 0, 0);
+    let mut uart: &[u8] = &stream;
+
+    assert_eq!(parser.read_frame(&mut uart).unwrap().unwrap().index, 9);
+    assert_eq!(parser.read_frame(&mut uart).unwrap(), None);
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use ceva_bno08x::BNO08x;
+use common::{NoDelay, Script};
+
+#[test]
+fn gaps_and_duplicates_are_counted_per_channel() {
+    let mut script = Script::default();
+    script.packet(3, 10, &[0xFB]);
+    script.packet(2, 0, &[0xF8]);
+    script.packet(3, 11, &[0xFB]);
+    // 12 and 13 lost
+    script.packet(3, 14, &[0xFB]);
+    script.packet(3, 14, &[0xFB]);
+    script.packet(2, 1, &[0xF8]);
+    let mut imu = BNO08x::new_uart(script, NoDelay);
+
+    for _ in 0..6 {
+        imu.read_packet().unwrap();
+    }
+
+    let input = imu.sequence_stats(3).unwrap();
+    assert_eq!(input.received, 4);
+    assert_eq!(input.gaps, 2);
+    assert_eq!(input.duplicates, 1);
+    assert_eq!(input.last, Some(14));
+
+    let control = imu.sequence_stats(2).unwrap();
+    assert_eq!((control.gaps, control.duplicates), (0, 0));
+}
+
+#[test]
+fn sequence_wraps_without_a_gap() {
+    let mut script = Script::default();
+    script.packet(3, 255, &[0xFB]);
+    script.packet(3, 0, &[0xFB]);
+    let mut imu = BNO08x::new_uart(script, NoDelay);
+
+    imu.read_packet().unwrap();
+    imu.read_packet().unwrap();
+
+    assert_eq!(imu.sequence_stats(3).unwrap().gaps, 0);
+}
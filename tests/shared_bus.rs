@@ -0,0 +1,75 @@
+mod common;
+
+use core::cell::RefCell;
+
+use ceva_bno08x::BNO08x;
+use ceva_bno08x::register::ReportId;
+use ceva_bno08x::sim::{SimError, SimulatedBno08x};
+use common::{ADDRESS, NoDelay, rotation_vector};
+use embedded_hal::i2c::{ErrorType, I2c, Operation};
+use embedded_hal_bus::i2c::RefCellDevice;
+
+/// Several simulated hubs wired to the same bus. A transfer goes to
+/// whichever hub acknowledges the address.
+struct Bus(Vec<SimulatedBno08x>);
+
+impl ErrorType for Bus {
+    type Error = SimError;
+}
+
+impl I2c for Bus {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        for hub in self.0.iter_mut() {
+            match hub.transaction(address, operations) {
+                Err(SimError::Nack) => continue,
+                result => return result,
+            }
+        }
+        Err(SimError::Nack)
+    }
+}
+
+#[test]
+fn two_hubs_share_one_bus() {
+    let mut first = SimulatedBno08x::new(ADDRESS);
+    let mut second = SimulatedBno08x::new(0x4B);
+    first.push_report(&rotation_vector(0x1000, 0));
+    second.push_report(&rotation_vector(0x2000, 0));
+    let bus = RefCell::new(Bus(vec![first, second]));
+
+    let mut a = BNO08x::new_with_address(RefCellDevice::new(&bus), NoDelay, ADDRESS);
+    let mut b = BNO08x::new_with_address(RefCellDevice::new(&bus), NoDelay, 0x4B);
+    a.enable_features(ReportId::RotationVector, Some(5000), None)
+        .unwrap();
+    b.enable_features(ReportId::GameRotationVector, Some(2500), None)
+        .unwrap();
+    b.enable_features(ReportId::RotationVector, Some(7500), None)
+        .unwrap();
+
+    assert_eq!(a.quaternions().unwrap().1, 0.25);
+    assert_eq!(b.quaternions().unwrap().1, 0.5);
+    // Each driver only counts the responses from its own hub
+    let control = a.channels().control;
+    assert_eq!(a.sequence_stats(control).unwrap().received, 5);
+    assert_eq!(b.sequence_stats(control).unwrap().received, 6);
+
+    drop((a, b));
+    let hubs = bus.into_inner().0;
+    assert_eq!(
+        hubs[0].feature_interval(ReportId::RotationVector),
+        Some(5000)
+    );
+    assert_eq!(hubs[0].feature_interval(ReportId::GameRotationVector), None);
+    assert_eq!(
+        hubs[1].feature_interval(ReportId::RotationVector),
+        Some(7500)
+    );
+    assert_eq!(
+        hubs[1].feature_interval(ReportId::GameRotationVector),
+        Some(2500)
+    );
+}
@@ -0,0 +1,142 @@
+mod common;
+
+use ceva_bno08x::error::I2cError;
+use ceva_bno08x::register::{FRSConfiguration, ReportId, Status};
+use ceva_bno08x::sim::{SimError, SimulatedBno08x};
+use ceva_bno08x::{BNO08x, SensorError};
+use common::{ADDRESS, NoDelay, rotation_vector};
+use embedded_hal::i2c::I2c;
+
+#[test]
+fn startup_advertisement_is_picked_up() {
+    let mut imu = BNO08x::new(SimulatedBno08x::new(ADDRESS), NoDelay, true);
+
+    imu.read_packet().unwrap();
+
+    let advertisement = imu.advertisement().unwrap();
+    assert_eq!(advertisement.sh2_version(), Some("1.0.0"));
+    assert_eq!(imu.channels().input_gyro_rv, 5);
+}
+
+#[test]
+fn answers_product_id_and_reset() {
+    let mut imu = BNO08x::new(SimulatedBno08x::new(ADDRESS), NoDelay, true);
+
+    assert_eq!(imu.read_product_id().ok(), Some(true));
+    assert!(imu.soft_reset_device().is_ok());
+
+    let (transport, _) = imu.release();
+    assert_eq!(transport.release().resets(), 2);
+}
+
+#[test]
+fn enabled_reports_are_streamed() {
+    let mut sim = SimulatedBno08x::new(ADDRESS);
+    sim.push_report(&rotation_vector(0x2000, 0x3800));
+    let mut imu = BNO08x::new(sim, NoDelay, true);
+
+    imu.enable_features(ReportId::RotationVector, Some(5000), None)
+        .unwrap();
+    let (status, i, _, _, real) = imu.quaternions().unwrap();
+
+    assert!(matches!(status, Status::HighAccuracy));
+    assert_eq!((i, real), (0.5, 0.875));
+    let (transport, _) = imu.release();
+    let sim = transport.release();
+    assert_eq!(sim.feature_interval(ReportId::RotationVector), Some(5000));
+    assert!(sim.feature_interval(ReportId::MagFieldCalibrated).is_some());
+}
+
+#[test]
+fn reports_for_disabled_features_are_dropped() {
+    let mut sim = SimulatedBno08x::new(ADDRESS);
+    sim.push_report(&rotation_vector(0x2000, 0x3800));
+    let mut imu = BNO08x::new(sim, NoDelay, true);
+
+    assert!(!imu.update_sensors().unwrap());
+}
+
+#[test]
+fn frs_records_are_served_two_words_at_a_time() {
+    let mut sim = SimulatedBno08x::new(ADDRESS);
+    sim.set_frs_record(FRSConfiguration::SystemOrientation, &[1, 2, 3]);
+    // Drain the startup messages
+    let mut buf = [0u8; 256];
+    for _ in 0..3 {
+        sim.read(ADDRESS, &mut buf).unwrap();
+    }
+
+    sim.write(ADDRESS, &[12, 0, 2, 0, 0xF4, 0, 0, 0, 0x3E, 0x2D, 0, 0])
+        .unwrap();
+
+    let mut first = [0u8; 20];
+    sim.read(ADDRESS, &mut first).unwrap();
+    assert_eq!(first[4], 0xF3);
+    assert_eq!(first[5], 0x20);
+    assert_eq!(first[8..16], [1, 0, 0, 0, 2, 0, 0, 0]);
+    let mut last = [0u8; 20];
+    sim.read(ADDRESS, &mut last).unwrap();
+    assert_eq!(last[5], 0x13);
+    assert_eq!(last[6..8], [2, 0]);
+    assert_eq!(last[16..18], [0x3E, 0x2D]);
+}
+
+#[test]
+fn frs_read_collects_the_whole_record() {
+    let mut sim = SimulatedBno08x::new(ADDRESS);
+    sim.set_frs_record(FRSConfiguration::SystemOrientation, &[1, 2, 3, 4, 5]);
+    let mut imu = BNO08x::new(sim, NoDelay, true);
+    let mut words = [0u32; 4];
+
+    let count = imu
+        .frs_read(FRSConfiguration::SystemOrientation, &mut words)
+        .unwrap();
+    let empty = imu
+        .frs_read(FRSConfiguration::GyroscopeOrientation, &mut words)
+        .unwrap();
+
+    // The fifth word doesn't fit and is dropped
+    assert_eq!(count, 5);
+    assert_eq!(words, [1, 2, 3, 4]);
+    assert_eq!(empty, 0);
+}
+
+#[test]
+fn short_reads_continue_in_the_next_transfer() {
+    let mut sim = SimulatedBno08x::new(ADDRESS);
+    let mut header = [0u8; 4];
+    sim.read(ADDRESS, &mut header).unwrap();
+    let length = u16::from_le_bytes([header[0], header[1]]);
+
+    let mut first = [0u8; 36];
+    sim.read(ADDRESS, &mut first).unwrap();
+    let mut rest = [0u8; 4];
+    sim.read(ADDRESS, &mut rest).unwrap();
+
+    assert_eq!(first[..4], header);
+    let continuation = u16::from_le_bytes([rest[0], rest[1]]);
+    assert_eq!(continuation, 0x8000 | (length - 32));
+    assert_eq!(rest[3], header[3].wrapping_add(1));
+}
+
+#[test]
+fn other_addresses_are_not_acknowledged() {
+    let mut sim = SimulatedBno08x::new(ADDRESS);
+
+    assert_eq!(sim.read(0x4B, &mut [0; 4]), Err(SimError::Nack));
+}
+
+#[test]
+fn bus_errors_reach_the_caller() {
+    // The driver talks to the alternate address, nobody answers there
+    let mut imu = BNO08x::new(SimulatedBno08x::new(ADDRESS), NoDelay, false);
+
+    assert!(matches!(
+        imu.read_product_id(),
+        Err(SensorError::Bus(I2cError::I2c(SimError::Nack)))
+    ));
+    assert!(matches!(
+        imu.enable_features(ReportId::RotationVector, None, None),
+        Err(SensorError::Bus(I2cError::I2c(SimError::Nack)))
+    ));
+}
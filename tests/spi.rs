@@ -0,0 +1,279 @@
+mod common;
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::rc::Rc;
+
+use ceva_bno08x::BNO08x;
+use ceva_bno08x::data::Packet;
+use ceva_bno08x::error::SpiError;
+use ceva_bno08x::transport::{SpiTransport, Transport};
+use common::NoDelay;
+use embedded_hal::digital::{ErrorType as PinErrorType, InputPin, OutputPin};
+use embedded_hal::spi::{ErrorType, Operation, SpiDevice};
+
+/// The hub side of the bus. Queued frames go out on the next transfers and
+/// INT is low while one is waiting or while WAKE is held low.
+#[derive(Default)]
+struct HubState {
+    queue: VecDeque<Vec<u8>>,
+    seq: [u8; 6],
+    written: Vec<Vec<u8>>,
+    transactions: usize,
+    /// Transactions started while INT was high
+    unsolicited: usize,
+    /// INT stays high for this many polls after every transaction
+    holdoff: u32,
+    wake: Vec<bool>,
+    reset: Vec<bool>,
+    /// Never pulls INT low, like a hub that is stuck or unpowered
+    unresponsive: bool,
+}
+
+impl HubState {
+    fn send(&mut self, channel: u8, cargo: &[u8]) {
+        let mut frame = Vec::new();
+        frame.extend((cargo.len() as u16 + 4).to_le_bytes());
+        frame.extend([channel, self.seq[channel as usize]]);
+        frame.extend(cargo);
+        self.seq[channel as usize] = self.seq[channel as usize].wrapping_add(1);
+        self.queue.push_back(frame);
+    }
+
+    fn int_asserted(&self) -> bool {
+        !self.unresponsive && (!self.queue.is_empty() || self.wake.last() == Some(&false))
+    }
+}
+
+#[derive(Clone, Default)]
+struct Hub(Rc<RefCell<HubState>>);
+
+impl ErrorType for Hub {
+    type Error = Infallible;
+}
+
+impl SpiDevice for Hub {
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+        let mut hub = self.0.borrow_mut();
+        hub.transactions += 1;
+        if hub.holdoff > 0 || !hub.int_asserted() {
+            hub.unsolicited += 1;
+        }
+
+        let outgoing = hub.queue.front().cloned().unwrap_or_default();
+        let mut incoming = Vec::new();
+        let mut clocked = 0;
+        for operation in operations.iter_mut() {
+            let (read, write): (&mut [u8], Vec<u8>) = match operation {
+                Operation::TransferInPlace(buf) => {
+                    let write = buf.to_vec();
+                    (buf, write)
+                }
+                Operation::Transfer(read, write) => (read, write.to_vec()),
+                Operation::Write(write) => (&mut [], write.to_vec()),
+                Operation::Read(read) => {
+                    let len = read.len();
+                    (read, vec![0; len])
+                }
+                Operation::DelayNs(_) => continue,
+            };
+            for (index, slot) in read.iter_mut().enumerate() {
+                *slot = outgoing.get(clocked + index).copied().unwrap_or(0);
+            }
+            clocked += read.len().max(write.len());
+            incoming.extend(write);
+        }
+
+        // A transfer that got through the whole frame consumes it, one that
+        // stopped early leaves the rest for a continuation
+        if !outgoing.is_empty() && clocked >= outgoing.len() {
+            hub.queue.pop_front();
+        } else if clocked >= 4 && !outgoing.is_empty() {
+            hub.queue.pop_front();
+            let rest = &outgoing[clocked..];
+            let channel = outgoing[2];
+            let mut frame = Vec::new();
+            frame.extend(((rest.len() as u16 + 4) | 0x8000).to_le_bytes());
+            frame.extend([channel, hub.seq[channel as usize]]);
+            frame.extend(rest);
+            hub.seq[channel as usize] = hub.seq[channel as usize].wrapping_add(1);
+            hub.queue.push_front(frame);
+        }
+        if incoming.len() >= 4 && u16::from_le_bytes([incoming[0], incoming[1]]) > 0 {
+            hub.written.push(incoming);
+        }
+        hub.holdoff = 2;
+        Ok(())
+    }
+}
+
+struct Int(Hub);
+
+impl PinErrorType for Int {
+    type Error = Infallible;
+}
+
+impl InputPin for Int {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        self.is_low().map(|low| !low)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        let mut hub = self.0.0.borrow_mut();
+        if hub.holdoff > 0 {
+            hub.holdoff -= 1;
+            return Ok(false);
+        }
+        Ok(hub.int_asserted())
+    }
+}
+
+struct Wake(Hub);
+
+impl PinErrorType for Wake {
+    type Error = Infallible;
+}
+
+impl OutputPin for Wake {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.0.0.borrow_mut().wake.push(false);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.0.0.borrow_mut().wake.push(true);
+        Ok(())
+    }
+}
+
+struct Nrst(Hub);
+
+impl PinErrorType for Nrst {
+    type Error = Infallible;
+}
+
+impl OutputPin for Nrst {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.0.0.borrow_mut().reset.push(false);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        let mut hub = self.0.0.borrow_mut();
+        hub.reset.push(true);
+        if hub.reset.ends_with(&[false, true]) {
+            hub.send(1, &[0x01]);
+        }
+        Ok(())
+    }
+}
+
+fn transport(hub: &Hub) -> SpiTransport<Hub, Int, Wake, Nrst> {
+    SpiTransport::new(
+        hub.clone(),
+        Int(hub.clone()),
+        Wake(hub.clone()),
+        Nrst(hub.clone()),
+    )
+}
+
+#[test]
+fn header_and_cargo_are_read_in_one_transaction() {
+    let hub = Hub::default();
+    hub.0.borrow_mut().send(3, &[0xFB, 1, 2, 3, 4]);
+    let mut spi = transport(&hub);
+
+    let mut out: Packet = spi.read_packet(&mut NoDelay, 300_000_000).unwrap();
+
+    assert_eq!((out.channel(), out.data_length()), (3, 5));
+    assert_eq!(out.as_mut_data(false), &[0xFB, 1, 2, 3, 4]);
+    let state = hub.0.borrow();
+    assert_eq!(state.transactions, 1);
+    assert_eq!(state.unsolicited, 0);
+    assert!(state.queue.is_empty());
+}
+
+#[test]
+fn writes_pulse_wake_and_return_what_the_hub_sent() {
+    let hub = Hub::default();
+    hub.0.borrow_mut().send(2, &[0xF8, 0, 3, 2]);
+    let mut spi = transport(&hub);
+
+    let mut received: Packet = spi
+        .write_packet(&[6, 0, 2, 1], &[0xF9, 0], &mut NoDelay)
+        .unwrap();
+
+    // Only the first two bytes of the cargo fit alongside the write
+    assert_eq!((received.channel(), received.data_length()), (2, 4));
+    assert_eq!(received.as_mut_data(false), &[0xF8, 0]);
+    let state = hub.0.borrow();
+    assert_eq!(state.written, [vec![6, 0, 2, 1, 0xF9, 0]]);
+    assert_eq!(state.wake, [false, true]);
+    assert_eq!(state.unsolicited, 0);
+    // The rest of the frame follows as a continuation
+    assert_eq!(state.queue[0][..4], [6, 0x80, 2, 1]);
+}
+
+#[test]
+fn response_clocked_in_during_a_write_is_kept() {
+    let hub = Hub::default();
+    let mut response = [0u8; 16];
+    response[..4].copy_from_slice(&[0xF8, 0, 3, 2]);
+    hub.0.borrow_mut().send(2, &response);
+    let mut imu: BNO08x<_, _> = BNO08x::new_spi(
+        hub.clone(),
+        Int(hub.clone()),
+        Wake(hub.clone()),
+        Nrst(hub.clone()),
+        NoDelay,
+    );
+
+    assert!(imu.read_product_id().unwrap());
+    // The response started alongside the request, its tail came as a
+    // continuation in the next read
+    let state = hub.0.borrow();
+    assert_eq!(state.written.len(), 1);
+    assert_eq!(state.transactions, 2);
+    assert_eq!(state.unsolicited, 0);
+}
+
+#[test]
+fn hard_reset_pulses_nrst_with_wake_released() {
+    let hub = Hub::default();
+    let mut spi = transport(&hub);
+
+    assert!(spi.hard_reset(&mut NoDelay).unwrap());
+
+    let state = hub.0.borrow();
+    assert_eq!(state.wake, [true]);
+    assert_eq!(state.reset, [true, false, true]);
+    // The reset complete that follows is left for the next read
+    assert_eq!(state.queue.len(), 1);
+}
+
+#[test]
+fn write_fails_when_the_hub_does_not_wake() {
+    let hub = Hub::default();
+    hub.0.borrow_mut().unresponsive = true;
+    let mut spi = transport(&hub);
+
+    let result: Result<Packet, _> = spi.write_packet(&[6, 0, 2, 1], &[0xF9, 0], &mut NoDelay);
+
+    assert!(matches!(result, Err(SpiError::Timeout)));
+    let state = hub.0.borrow();
+    assert_eq!(state.transactions, 0);
+    assert_eq!(state.wake, [false, true]);
+}
+
+#[test]
+fn hard_reset_fails_when_the_hub_stays_silent() {
+    let hub = Hub::default();
+    hub.0.borrow_mut().unresponsive = true;
+    let mut spi = transport(&hub);
+
+    assert!(matches!(
+        spi.hard_reset(&mut NoDelay),
+        Err(SpiError::Timeout)
+    ));
+}
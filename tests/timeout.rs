@@ -0,0 +1,114 @@
+use std::convert::Infallible;
+
+use ceva_bno08x::capture::ReplayTransport;
+use ceva_bno08x::register::ReportId;
+use ceva_bno08x::sim::SimulatedBno08x;
+use ceva_bno08x::{BNO08x, SensorError, Timeouts};
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{ErrorType, InputPin};
+
+/// Keeps track of how long the driver has been waiting.
+#[derive(Default)]
+struct Clock {
+    elapsed_ns: u64,
+}
+
+impl DelayNs for Clock {
+    fn delay_ns(&mut self, ns: u32) {
+        self.elapsed_ns += ns as u64;
+    }
+}
+
+/// A hub that never says anything.
+fn silent_hub() -> BNO08x<ReplayTransport<&'static [u8]>, Clock> {
+    BNO08x::from_transport(ReplayTransport::new(&[][..]), Clock::default())
+}
+
+/// An INT line the hub never pulls low.
+struct SilentInt;
+
+impl ErrorType for SilentInt {
+    type Error = Infallible;
+}
+
+impl InputPin for SilentInt {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(true)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(false)
+    }
+}
+
+#[test]
+fn silent_hub_times_out_product_id() {
+    let mut imu = silent_hub();
+
+    assert!(matches!(
+        imu.read_product_id(),
+        Err(SensorError::Timeout {
+            channel: 2,
+            report_id: Some(0xF8)
+        })
+    ));
+    let (_, clock) = imu.release();
+    assert_eq!(clock.elapsed_ns, 300 * 1_000_000);
+}
+
+#[test]
+fn configured_timeouts_are_used() {
+    let mut imu = silent_hub();
+    imu.set_timeouts(Timeouts {
+        response_ms: 20,
+        reset_ms: 50,
+    });
+
+    assert!(matches!(
+        imu.enable_features(ReportId::AccelerometerCalibrated, None, None),
+        Err(SensorError::Timeout {
+            channel: 2,
+            report_id: Some(0xFC)
+        })
+    ));
+    assert!(matches!(
+        imu.soft_reset_device(),
+        Err(SensorError::Timeout {
+            channel: 1,
+            report_id: Some(0x01)
+        })
+    ));
+    let (_, clock) = imu.release();
+    assert_eq!(clock.elapsed_ns, 70 * 1_000_000);
+}
+
+#[test]
+fn missing_input_reports_are_not_an_error() {
+    let mut imu = silent_hub();
+
+    assert!(!imu.update_sensors().unwrap());
+}
+
+#[test]
+fn waiting_on_int_counts_towards_the_timeout() {
+    let mut imu: BNO08x<_, _> = BNO08x::new_with_interrupt(
+        SimulatedBno08x::new(0x4A),
+        SilentInt,
+        Clock::default(),
+        true,
+    );
+    imu.set_timeouts(Timeouts {
+        response_ms: 20,
+        reset_ms: 50,
+    });
+
+    assert!(matches!(
+        imu.read_product_id(),
+        Err(SensorError::Timeout { channel: 2, .. })
+    ));
+    assert!(!imu.update_sensors().unwrap());
+    let (_, clock) = imu.release();
+    // 20 ms for the response and 10 ms for input reports, plus at most one
+    // INT poll each
+    assert!((30_000_000..30_010_000).contains(&clock.elapsed_ns));
+}
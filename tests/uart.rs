@@ -0,0 +1,144 @@
+mod common;
+
+use std::collections::VecDeque;
+use std::convert::Infallible;
+
+use ceva_bno08x::data::{Packet, PacketError};
+use ceva_bno08x::error::UartError;
+use ceva_bno08x::transport::{Transport, UartTransport};
+use common::NoDelay;
+
+/// Everything written comes straight back out on the next read. Running
+/// out of bytes mid read is end of file.
+#[derive(Default)]
+struct Loopback {
+    bytes: VecDeque<u8>,
+    reads: usize,
+}
+
+impl embedded_io::ErrorType for Loopback {
+    type Error = Infallible;
+}
+
+impl embedded_io::Read for Loopback {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.reads += 1;
+        let mut count = 0;
+        while count < buf.len() {
+            match self.bytes.pop_front() {
+                Some(byte) => buf[count] = byte,
+                None => break,
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+impl embedded_io::ReadReady for Loopback {
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(!self.bytes.is_empty())
+    }
+}
+
+impl embedded_io::Write for Loopback {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.bytes.extend(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[test]
+fn frames_round_trip_through_loopback() {
+    let mut uart = UartTransport::new(Loopback::default());
+    let cargo = [0xF9, 0x00, 0x7E, 0x7D, 0x11];
+    let _: Packet = uart
+        .write_packet(&[9, 0, 2, 7], &cargo, &mut NoDelay)
+        .unwrap();
+    let mut read: Packet = uart.read_packet(&mut NoDelay, 0).unwrap();
+
+    assert_eq!(read.channel(), 2);
+    assert_eq!(read.seq_num(), 7);
+    assert_eq!(read.packet_length(), 9);
+    assert_eq!(read.as_mut_data(false), &cargo);
+}
+
+#[test]
+fn flag_and_escape_bytes_are_escaped_on_the_wire() {
+    let mut uart = UartTransport::new(Loopback::default());
+    let _: Packet = uart
+        .write_packet(&[6, 0, 1, 0], &[0x7E, 0x7D], &mut NoDelay)
+        .unwrap();
+    let wire: Vec<u8> = uart.release().bytes.into_iter().collect();
+
+    assert_eq!(
+        wire,
+        [
+            0x7E, 0x01, 0x06, 0x00, 0x01, 0x00, 0x7D, 0x5E, 0x7D, 0x5D, 0x7E
+        ]
+    );
+}
+
+#[test]
+fn non_shtp_frames_and_noise_are_skipped() {
+    let mut loopback = Loopback::default();
+    // Line noise, then a buffer status reply, then a real frame
+    loopback.bytes.extend([0x55, 0x7E, 0x00, 0x10, 0x00, 0x7E]);
+    loopback
+        .bytes
+        .extend([0x7E, 0x01, 0x05, 0x00, 0x03, 0x02, 0xAA, 0x7E]);
+    let mut uart = UartTransport::new(loopback);
+
+    let mut read: Packet = uart.read_packet(&mut NoDelay, 0).unwrap();
+
+    assert_eq!(read.channel(), 3);
+    assert_eq!(read.seq_num(), 2);
+    assert_eq!(read.as_mut_data(false), &[0xAA]);
+}
+
+#[test]
+fn empty_stream_yields_empty_packet() {
+    let mut uart = UartTransport::new(Loopback::default());
+
+    let read: Packet = uart.read_packet(&mut NoDelay, 0).unwrap();
+
+    assert_eq!(read.packet_length(), 0);
+    // A blocking read with nothing ready would have hung
+    assert_eq!(uart.release().reads, 0);
+}
+
+#[test]
+fn truncated_frame_is_an_error() {
+    let mut loopback = Loopback::default();
+    loopback
+        .bytes
+        .extend([0x7E, 0x01, 0x08, 0x00, 0x02, 0x00, 0xF8]);
+    let mut uart = UartTransport::new(loopback);
+
+    let read: Result<Packet, _> = uart.read_packet(&mut NoDelay, 0);
+    assert!(read.is_err());
+}
+
+#[test]
+fn oversized_frame_is_skipped() {
+    let mut uart = UartTransport::new(Loopback::default());
+    let _: Packet = uart
+        .write_packet(&[24, 0, 2, 0], &[0xF3; 20], &mut NoDelay)
+        .unwrap();
+    let _: Packet = uart
+        .write_packet(&[6, 0, 2, 1], &[0xF8, 0x00], &mut NoDelay)
+        .unwrap();
+
+    let read: Result<Packet<16>, _> = uart.read_packet(&mut NoDelay, 0);
+    assert!(matches!(
+        read,
+        Err(UartError::Packet(PacketError::TooLarge))
+    ));
+    let mut read: Packet<16> = uart.read_packet(&mut NoDelay, 0).unwrap();
+    assert_eq!(read.seq_num(), 1);
+    assert_eq!(read.as_mut_data(false), &[0xF8, 0x00]);
+}